@@ -0,0 +1,447 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use anyhow::{self, Result, Context};
+use crate::archive::archive_utils::{output_path, set_mode, set_modified};
+
+// Global header every ar archive starts with
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+// Terminator of each 60 byte member header
+const HEADER_END: &[u8] = b"`\n";
+
+// Prefix of BSD names stored in front of the member data
+const BSD_LONG_NAME: &str = "#1/";
+
+// Suffixes stripped from an archive name to build the default output folder name
+const AR_SUFFIXES: &[&str] = &[".a", ".ar", ".lib"];
+
+/// Metadata of a single ar member.
+pub struct ArMember {
+    pub name: String,
+    pub mtime: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+    pub size: u64,
+}
+
+pub fn ar_folder(
+    folder_dir: &Path,
+    file_name: Option<&str>,
+    variant: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    // Ensure the folder exists
+    if !folder_dir.is_dir() {
+        anyhow::bail!("The provided folder path does not exist or is not a directory.");
+    }
+
+    let gnu = match variant {
+        None | Some("gnu") => true,
+        Some("bsd") => false,
+        Some(invalid) => {
+            anyhow::bail!("Invalid ar variant: '{}'", invalid);
+        }
+    };
+
+    // ar archives are flat, so only the files directly inside the folder are
+    // stored, in name order so the archive is reproducible
+    let mut files = Vec::new();
+    for entry in fs::read_dir(folder_dir)
+        .with_context(|| format!("Failed to read directory {:?}", folder_dir))?
+    {
+        let path = entry.with_context(|| "Failed to read directory entry")?.path();
+        if path.is_dir() {
+            anyhow::bail!("ar archives cannot hold directories: {:?}", path);
+        }
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid file name: {:?}", path))?
+            .to_string();
+        files.push((name, path));
+    }
+    files.sort();
+
+    // Get the folder's name to use as the base file name
+    let folder_name = folder_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine folder name"))?;
+    let base_name = file_name.unwrap_or(folder_name);
+
+    // Determine the output path
+    let output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => folder_dir.parent().unwrap_or_else(|| Path::new(".")).to_path_buf(),
+    };
+
+    // Resolve conflicts with existing file names by appending a number
+    let mut full_output_path = output_path.join(format!("{}.a", base_name));
+    let mut counter = 1;
+    while full_output_path.exists() {
+        full_output_path = output_path.join(format!("{}-{}.a", base_name, counter));
+        counter += 1;
+    }
+
+    // Create the ar archive
+    let ar_file = File::create(&full_output_path)
+        .with_context(|| format!("Failed to create ar file at {:?}", full_output_path))?;
+    let mut writer = BufWriter::new(ar_file);
+    writer.write_all(AR_MAGIC)?;
+
+    // GNU ar keeps names that do not fit in the header in a "//" member,
+    // while BSD ar stores long names and names with spaces in front of the data
+    let mut long_names = String::new();
+    let mut header_names = Vec::new();
+    for (name, _) in &files {
+        if gnu && name.len() < 16 {
+            header_names.push(format!("{}/", name));
+        } else if gnu {
+            header_names.push(format!("/{}", long_names.len()));
+            long_names.push_str(name);
+            long_names.push_str("/\n");
+        } else if name.len() > 15 || name.contains(' ') {
+            header_names.push(format!("{}{}", BSD_LONG_NAME, name.len()));
+        } else {
+            header_names.push(name.clone());
+        }
+    }
+    if !long_names.is_empty() {
+        let table = ArMember {
+            name: "//".to_string(),
+            mtime: 0,
+            uid: 0,
+            gid: 0,
+            mode: 0,
+            size: long_names.len() as u64,
+        };
+        write_header(&mut writer, "//", &table)?;
+        writer.write_all(long_names.as_bytes())?;
+        write_padding(&mut writer, table.size)?;
+    }
+
+    for ((name, path), header_name) in files.iter().zip(header_names) {
+        let metadata = fs::metadata(path)?;
+        let mut member = ArMember {
+            name: name.clone(),
+            mtime: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs()),
+            uid: 0,
+            gid: 0,
+            mode: 0o100644,
+            size: metadata.len(),
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            member.uid = metadata.uid();
+            member.gid = metadata.gid();
+            member.mode = metadata.mode();
+        }
+
+        if header_name.starts_with(BSD_LONG_NAME) {
+            member.size += name.len() as u64;
+            write_header(&mut writer, &header_name, &member)?;
+            writer.write_all(name.as_bytes())?;
+        } else {
+            write_header(&mut writer, &header_name, &member)?;
+        }
+
+        let mut file = File::open(path)
+            .with_context(|| format!("Failed to open file {:?}", path))?;
+        let copied = io::copy(&mut file, &mut writer)?;
+        if copied != metadata.len() {
+            anyhow::bail!("File {:?} changed size while being archived", path);
+        }
+        write_padding(&mut writer, member.size)?;
+    }
+
+    writer.flush().with_context(|| "Failed to finalize the ar archive")?;
+    Ok(full_output_path)
+}
+
+// Writes a 60 byte member header. The name is padded by hand, as format
+// widths count characters and a UTF-8 name may be shorter in characters than
+// in bytes
+fn write_header<W: Write>(writer: &mut W, header_name: &str, member: &ArMember) -> Result<()> {
+    let padding = " ".repeat(16usize.saturating_sub(header_name.len()));
+    let header = format!(
+        "{}{}{:<12}{:<6}{:<6}{:<8o}{:<10}",
+        header_name, padding, member.mtime, member.uid, member.gid, member.mode, member.size
+    );
+    if header.len() != 58 {
+        anyhow::bail!("{:?} does not fit in an ar header", member.name);
+    }
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(HEADER_END)?;
+    Ok(())
+}
+
+// Members start on even offsets, so odd sized data is followed by a newline
+fn write_padding<W: Write>(writer: &mut W, size: u64) -> Result<()> {
+    if size % 2 == 1 {
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Returns the members stored in an ar archive, in archive order.
+pub fn list_ar_file(ar_file_path: &Path) -> Result<Vec<ArMember>> {
+    if !ar_file_path.is_file() {
+        anyhow::bail!("The provided ar file path does not exist or is not a file.");
+    }
+
+    let ar_file = File::open(ar_file_path)
+        .with_context(|| format!("Failed to open ar file {:?}", ar_file_path))?;
+    let mut members = Vec::new();
+    for_each_member(BufReader::new(ar_file), |member, _| {
+        members.push(member);
+        Ok(true)
+    })?;
+    Ok(members)
+}
+
+pub fn unar_file(
+    ar_file_path: &Path,
+    file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    // Ensure the input ar file exists
+    if !ar_file_path.is_file() {
+        anyhow::bail!("The provided ar file path does not exist or is not a file.");
+    }
+
+    // Get the ar file's name to use as the base name if no name is provided
+    let ar_file_name = ar_file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine ar file name"))?;
+
+    let base_name = file_name.unwrap_or_else(|| {
+        AR_SUFFIXES
+            .iter()
+            .find_map(|suffix| ar_file_name.strip_suffix(suffix))
+            .unwrap_or(ar_file_name)
+    });
+
+    // Determine the base output directory
+    let base_output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => ar_file_path.parent().unwrap_or_else(|| Path::new(".")).join(format!("{}_extracted", base_name)),
+    };
+
+    // Resolve conflicts by appending a number to the output directory
+    let mut output_dir = base_output_path.clone();
+    let mut counter = 1;
+    while output_dir.exists() {
+        output_dir = base_output_path.with_file_name(format!(
+            "{}-{}",
+            base_output_path.file_name().unwrap().to_str().unwrap(),
+            counter
+        ));
+        counter += 1;
+    }
+
+    // Create the output directory
+    fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Failed to create output directory {:?}", output_dir))?;
+
+    // Extract each member; later members of the same name replace earlier ones, as with `ar x`
+    let ar_file = File::open(ar_file_path)
+        .with_context(|| format!("Failed to open ar file {:?}", ar_file_path))?;
+    for_each_member(BufReader::new(ar_file), |member, data| {
+        let out_path = output_path(&output_dir, &member.name)?;
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut outfile = BufWriter::new(
+            File::create(&out_path)
+                .with_context(|| format!("Failed to create file {:?}", out_path))?,
+        );
+        io::copy(data, &mut outfile)?;
+        outfile.into_inner().map_err(|err| err.into_error())?;
+        set_modified(&out_path, Some(UNIX_EPOCH + Duration::from_secs(member.mtime)))?;
+        set_mode(&out_path, Some(member.mode))?;
+        Ok(true)
+    })?;
+
+    Ok(output_dir)
+}
+
+/// Calls `each` with every regular member of a GNU or BSD ar archive and a
+/// reader over its data, stopping early when `each` returns `false`. Symbol
+/// tables are skipped and long names are resolved.
+pub fn for_each_member<R, F>(mut reader: R, mut each: F) -> Result<()>
+where
+    R: Read,
+    F: FnMut(ArMember, &mut dyn Read) -> Result<bool>,
+{
+    let mut magic = [0; 8];
+    reader
+        .read_exact(&mut magic)
+        .with_context(|| "Not an ar archive")?;
+    if magic != AR_MAGIC {
+        anyhow::bail!("Not an ar archive");
+    }
+
+    let mut long_names: Vec<u8> = Vec::new();
+    loop {
+        // Read the next header, stopping cleanly at the end of the archive
+        let mut header = [0; 60];
+        let read = read_full(&mut reader, &mut header)?;
+        if read == 0 {
+            break;
+        } else if read < header.len() || &header[58..] != HEADER_END {
+            anyhow::bail!("Malformed ar member header");
+        }
+
+        let field = |start: usize, len: usize| {
+            String::from_utf8_lossy(&header[start..start + len]).trim_end().to_string()
+        };
+        let number = |start: usize, len: usize, radix: u32| -> Result<u64> {
+            let text = field(start, len);
+            if text.is_empty() {
+                return Ok(0);
+            }
+            u64::from_str_radix(&text, radix)
+                .map_err(|_| anyhow::anyhow!("Malformed ar header field: {:?}", text))
+        };
+
+        let raw_name = field(0, 16);
+        let stored_size = number(48, 10, 10)?;
+        let mut member = ArMember {
+            name: String::new(),
+            mtime: number(16, 12, 10)?,
+            uid: number(28, 6, 10)? as u32,
+            gid: number(34, 6, 10)? as u32,
+            mode: number(40, 8, 8)? as u32,
+            size: stored_size,
+        };
+        let mut data = (&mut reader).take(member.size);
+
+        if raw_name == "//" {
+            // GNU long name table
+            long_names.clear();
+            data.read_to_end(&mut long_names)?;
+        } else if raw_name == "/" || raw_name == "/SYM64/" {
+            // Symbol tables are regenerated by ranlib, so they are not extracted
+        } else {
+            member.name = if let Some(offset) = raw_name.strip_prefix('/') {
+                let offset: usize = offset
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Malformed GNU long name {:?}", raw_name))?;
+                let name = long_names
+                    .get(offset..)
+                    .and_then(|rest| rest.split(|byte| *byte == b'\n').next())
+                    .ok_or_else(|| anyhow::anyhow!("GNU long name {:?} is out of range", raw_name))?;
+                String::from_utf8_lossy(name).trim_end_matches('/').to_string()
+            } else if let Some(length) = raw_name.strip_prefix(BSD_LONG_NAME) {
+                let length: u64 = length
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Malformed BSD long name {:?}", raw_name))?;
+                if length > member.size {
+                    anyhow::bail!("BSD long name {:?} is larger than its member", raw_name);
+                }
+                let mut name = vec![0; length as usize];
+                data.read_exact(&mut name)?;
+                member.size -= length;
+                String::from_utf8_lossy(&name).trim_end_matches('\0').to_string()
+            } else {
+                raw_name.strip_suffix('/').unwrap_or(&raw_name).to_string()
+            };
+
+            // BSD symbol tables may sit behind a long name such as
+            // "__.SYMDEF SORTED", so they are only recognised once resolved
+            if !member.name.starts_with("__.SYMDEF") && !each(member, &mut data)? {
+                return Ok(());
+            }
+        }
+
+        // Skip whatever the callback did not consume, then the padding byte
+        io::copy(&mut data, &mut io::sink())?;
+        if data.limit() > 0 {
+            anyhow::bail!("Unexpected end of ar archive");
+        }
+        if stored_size % 2 == 1 {
+            let mut padding = [0; 1];
+            read_full(&mut reader, &mut padding)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Fills `buf` as far as the reader allows, returning the number of bytes read
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Names shorter than 16 characters but not 16 bytes, alongside ones
+    // that need the long name storage of each variant
+    const NAMES: &[&str] = &["ü.txt", "ééééé.o", "日本語日本語日本.o", "plain.o"];
+
+    #[test]
+    fn round_trips_multibyte_names() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = root.path().join("objects");
+        fs::create_dir(&folder).unwrap();
+        for (index, name) in NAMES.iter().enumerate() {
+            fs::write(folder.join(name), "x".repeat(index + 1)).unwrap();
+        }
+
+        for variant in ["gnu", "bsd"] {
+            let archive = ar_folder(&folder, Some(variant), Some(variant), Some(root.path().to_str().unwrap())).unwrap();
+            let mut names: Vec<_> = list_ar_file(&archive).unwrap().into_iter().map(|member| member.name).collect();
+            names.sort();
+            let mut expected: Vec<_> = NAMES.iter().map(|name| name.to_string()).collect();
+            expected.sort();
+            assert_eq!(names, expected, "{}", variant);
+
+            let output = root.path().join(format!("{}-out", variant));
+            let output = unar_file(&archive, None, Some(output.to_str().unwrap())).unwrap();
+            for (index, name) in NAMES.iter().enumerate() {
+                assert_eq!(fs::read_to_string(output.join(name)).unwrap(), "x".repeat(index + 1), "{}", variant);
+            }
+        }
+    }
+
+    #[test]
+    fn skips_bsd_symbol_tables_with_long_names() {
+        let mut archive = AR_MAGIC.to_vec();
+        for (header_name, data) in [("#1/20", &b"__.SYMDEF SORTED\0\0\0\0symbols!"[..]), ("a.o", &b"object"[..])] {
+            archive.extend_from_slice(format!("{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}", header_name, 0, 0, 0, 0o100644, data.len()).as_bytes());
+            archive.extend_from_slice(HEADER_END);
+            archive.extend_from_slice(data);
+        }
+
+        let mut names = Vec::new();
+        for_each_member(&archive[..], |member, _| {
+            names.push(member.name);
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(names, ["a.o"]);
+    }
+}
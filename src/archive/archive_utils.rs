@@ -0,0 +1,132 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// Helpers shared by the extractors of the multi-file archive formats
+use std::fs::{self, File};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::{self, Result, Context};
+
+/// Picks the folder an archive is extracted into: `base_name` inside
+/// `base_dir`, or `base_name-1`, `base_name-2` and so on in the same place
+/// when that name is taken.
+pub fn extraction_dir(base_dir: &Path, base_name: &str) -> PathBuf {
+    let mut output_dir = base_dir.join(base_name);
+    let mut counter = 1;
+    while output_dir.exists() {
+        output_dir = base_dir.join(format!("{}-{}", base_name, counter));
+        counter += 1;
+    }
+    output_dir
+}
+
+/// Joins an archive entry name onto `output_dir`, refusing names that would
+/// escape it, either directly or through a symlink extracted earlier. A
+/// symlink already sitting at the path itself is removed so that writing
+/// the entry cannot follow it.
+pub fn output_path(output_dir: &Path, name: &str) -> Result<PathBuf> {
+    checked_path(output_dir, name, true)
+}
+
+/// Resolves the existing entry a hard link points at inside `output_dir`,
+/// refusing names that would reach it through a symlink or escape it.
+pub fn link_path(output_dir: &Path, name: &str) -> Result<PathBuf> {
+    checked_path(output_dir, name, false)
+}
+
+// Joins `name` onto `output_dir` component by component, refusing to pass
+// through symlinks and optionally removing one found at the path itself
+fn checked_path(output_dir: &Path, name: &str, replace_symlink: bool) -> Result<PathBuf> {
+    let relative = entry_path(name)?;
+    let mut path = output_dir.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        path.push(component);
+        let is_symlink = path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if is_symlink && components.peek().is_some() {
+            anyhow::bail!("Refusing to extract {:?} through a symlink", name);
+        } else if is_symlink && replace_symlink {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to replace symlink {:?}", path))?;
+        }
+    }
+    Ok(path)
+}
+
+// Turns an entry name into a relative path, refusing names that would
+// escape the output directory; leading slashes are dropped as tar does
+fn entry_path(name: &str) -> Result<PathBuf> {
+    let name = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir | Component::RootDir => {}
+            _ => anyhow::bail!("Refusing to extract unsafe path {:?}", name),
+        }
+    }
+    Ok(path)
+}
+
+/// Applies a stored modification time to an extracted file or directory.
+pub fn set_modified(path: &Path, modified: Option<SystemTime>) -> Result<()> {
+    if let Some(modified) = modified {
+        File::open(path)
+            .and_then(|file| file.set_modified(modified))
+            .with_context(|| format!("Failed to set modification time on {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Applies the permission bits of a stored Unix mode to an extracted file
+/// or directory.
+pub fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+            .with_context(|| format!("Failed to set permissions on {:?}", path))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+/// Hands an extracted entry to the stored owner. Without the privileges
+/// to do so the entry is left owned by the current user.
+pub fn set_owner(path: &Path, uid: u32, gid: u32) -> Result<()> {
+    #[cfg(unix)]
+    if let Err(err) = std::os::unix::fs::lchown(path, Some(uid), Some(gid)) {
+        if err.kind() != std::io::ErrorKind::PermissionDenied {
+            return Err(err).with_context(|| format!("Failed to set owner of {:?}", path));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (path, uid, gid);
+    Ok(())
+}
+
+/// Creates a device node, FIFO or socket from a full Unix mode. Returns
+/// `false` when the node could not be created for lack of privileges.
+pub fn make_node(path: &Path, mode: u32, major: u32, minor: u32) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+        let device = libc::makedev(major as _, minor as _);
+        if unsafe { libc::mknod(c_path.as_ptr(), mode as libc::mode_t, device) } == 0 {
+            return Ok(true);
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() == std::io::ErrorKind::PermissionDenied {
+            return Ok(false);
+        }
+        Err(err).with_context(|| format!("Failed to create special file {:?}", path))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode, major, minor);
+        Ok(false)
+    }
+}
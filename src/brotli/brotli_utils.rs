@@ -0,0 +1,167 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use brotli::Decompressor;
+use brotli::enc::{BrotliCompress, BrotliEncoderParams};
+use walkdir::WalkDir;
+use anyhow::{self, Result};
+
+// Internal buffer size handed to the brotli decoder
+const BUFFER_SIZE: usize = 4096;
+
+// Extensions of formats that are already compressed, which brotli would
+// only make larger
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "br", "gz", "tgz", "zst", "xz", "txz", "bz2", "lz4", "lz", "lzma", "z", "zip", "7z", "rar", "jar",
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "heic", "woff", "woff2", "mp3", "mp4", "m4a", "ogg",
+    "webm", "mkv", "mov",
+];
+
+pub fn brotli_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    quality: Option<u32>,
+    window_size: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let (quality, window_size) = check_params(quality, window_size)?;
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let br_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.br", file_name)));
+
+    let br_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(br_name);
+
+    compress(input_path, &br_path, quality, window_size)?;
+
+    Ok(br_path)
+}
+
+/// Compresses every file under `folder_dir` into a sibling `.br` file,
+/// skipping files that are already compressed. With `output_dir`, the
+/// `.br` files are written there instead, mirroring the folder's layout.
+pub fn brotli_folder(
+    folder_dir: &Path,
+    quality: Option<u32>,
+    window_size: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<Vec<PathBuf>> {
+    if !folder_dir.is_dir() {
+        anyhow::bail!(
+            "Provided path is not a directory, or does not exist: {:?}",
+            folder_dir
+        );
+    }
+
+    let (quality, window_size) = check_params(quality, window_size)?;
+
+    let mut br_paths = Vec::new();
+    for entry in WalkDir::new(folder_dir).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.is_file() || is_compressed(path) {
+            continue;
+        }
+
+        let mut br_path = match output_dir {
+            Some(output_dir) => Path::new(output_dir).join(path.strip_prefix(folder_dir)?).into_os_string(),
+            None => path.as_os_str().to_owned(),
+        };
+        br_path.push(".br");
+        let br_path = PathBuf::from(br_path);
+        if let Some(parent) = br_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        compress(path, &br_path, quality, window_size)?;
+        br_paths.push(br_path);
+    }
+
+    Ok(br_paths)
+}
+
+pub fn unbrotli_file(
+    br_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !br_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", br_path);
+    }
+
+    let default_name = br_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .br file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| br_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(br_path)?;
+    let mut decoder = Decompressor::new(BufReader::new(input_file), BUFFER_SIZE);
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
+
+// Validates the quality (0-11) and window size (10-24), applying the defaults
+fn check_params(quality: Option<u32>, window_size: Option<u32>) -> Result<(u32, u32)> {
+    let quality = quality.unwrap_or(11);
+    if quality > 11 {
+        anyhow::bail!("Invalid brotli quality: {} (expected 0 to 11)", quality);
+    }
+
+    let window_size = window_size.unwrap_or(22);
+    if !(10..=24).contains(&window_size) {
+        anyhow::bail!("Invalid brotli window size: {} (expected 10 to 24)", window_size);
+    }
+
+    Ok((quality, window_size))
+}
+
+// Whether a file's extension marks it as already compressed
+fn is_compressed(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| COMPRESSED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+// BrotliCompress reports failures while finishing the stream, which a
+// dropped CompressorWriter would swallow
+fn compress(input_path: &Path, br_path: &Path, quality: u32, window_size: u32) -> Result<()> {
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(br_path)?;
+
+    let params = BrotliEncoderParams {
+        quality: quality as i32,
+        lgwin: window_size as i32,
+        ..Default::default()
+    };
+    let mut output = BufWriter::new(output_file);
+    BrotliCompress(&mut BufReader::new(input_file), &mut output, &params)?;
+    output.flush()?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,89 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use bzip2::Compression;
+use bzip2::read::MultiBzDecoder;
+use bzip2::write::BzEncoder;
+use anyhow::{self, Result};
+
+pub fn bzip2_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let compression = compression(compression_level)?;
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let bz2_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.bz2", file_name)));
+
+    let bz2_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(bz2_name);
+
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(&bz2_path)?;
+
+    let mut encoder = BzEncoder::new(BufWriter::new(output_file), compression);
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.finish()?;
+
+    Ok(bz2_path)
+}
+
+pub fn bunzip2_file(
+    bz2_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !bz2_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", bz2_path);
+    }
+
+    let default_name = bz2_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .bz2 file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| bz2_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(bz2_path)?;
+    let mut decoder = MultiBzDecoder::new(BufReader::new(input_file));
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
+
+/// Maps a 0-9 level onto libbz2's 1-9 block sizes; level 0 has no store
+/// mode in bzip2, so it is treated as the fastest setting.
+pub fn compression(compression_level: Option<u32>) -> Result<Compression> {
+    let level = compression_level.unwrap_or(6);
+    if level > 9 {
+        anyhow::bail!("Invalid bzip2 compression level: {} (expected 0 to 9)", level);
+    }
+
+    Ok(Compression::new(level.max(1)))
+}
\ No newline at end of file
@@ -0,0 +1,3 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+pub mod bzip2_utils;
\ No newline at end of file
@@ -0,0 +1,524 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use flate2::read::DeflateDecoder;
+use anyhow::{self, Result, Context};
+use crate::archive::archive_utils::{extraction_dir, output_path, set_mode, set_modified};
+use super::lzx::LzxDecoder;
+
+// Cabinet header constants
+const CAB_SIGNATURE: &[u8] = b"MSCF";
+const HEADER_SIZE: usize = 36;
+const FLAG_PREV_CABINET: u16 = 0x0001;
+const FLAG_NEXT_CABINET: u16 = 0x0002;
+const FLAG_RESERVE_PRESENT: u16 = 0x0004;
+
+// Folder indices of files continued from or into another cabinet
+const FOLDER_CONTINUED: u16 = 0xfffd;
+
+// Compression types of a folder
+const COMPRESSION_MASK: u16 = 0x000f;
+const COMPRESSION_NONE: u16 = 0;
+const COMPRESSION_MSZIP: u16 = 1;
+const COMPRESSION_QUANTUM: u16 = 2;
+const COMPRESSION_LZX: u16 = 3;
+
+// File attributes
+const ATTRIBUTE_READ_ONLY: u16 = 0x01;
+const ATTRIBUTE_EXECUTE: u16 = 0x40;
+const ATTRIBUTE_NAME_IS_UTF: u16 = 0x80;
+
+// MSZIP blocks are deflate streams sharing a 32 KiB history
+const MSZIP_SIGNATURE: &[u8] = b"CK";
+const MSZIP_WINDOW: usize = 32768;
+const MAX_BLOCK_SIZE: usize = 32768;
+
+/// A file stored in a cabinet.
+pub struct CabEntry {
+    /// Path inside the cabinet, with `/` separators.
+    pub name: String,
+    pub size: u32,
+    pub modified: Option<SystemTime>,
+    pub attributes: u16,
+    folder: u16,
+    offset: u32,
+}
+
+// Where the data blocks of a folder start and how they are compressed
+struct Folder {
+    data_start: u64,
+    data_blocks: u16,
+    compression: u16,
+}
+
+// The parsed directory of a cabinet
+struct Cabinet {
+    folders: Vec<Folder>,
+    entries: Vec<CabEntry>,
+    data_reserve: usize,
+}
+
+// Per folder decompression state
+enum Decoder {
+    Stored,
+    Mszip(Vec<u8>),
+    Lzx(Box<LzxDecoder>),
+}
+
+// Reads the uncompressed contents of a folder block by block
+struct FolderReader {
+    file: BufReader<File>,
+    blocks_left: u16,
+    data_reserve: usize,
+    decoder: Decoder,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+/// Checks whether a file starts with the cabinet signature.
+pub fn is_cab_file(path: &Path) -> bool {
+    let mut signature = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut signature))
+        .is_ok_and(|_| signature == CAB_SIGNATURE)
+}
+
+pub fn list_cab_file(cab_file_path: &Path) -> Result<Vec<CabEntry>> {
+    // Ensure the file exists and is a valid file
+    if !cab_file_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", cab_file_path);
+    }
+
+    Ok(read_cabinet(cab_file_path)?.entries)
+}
+
+pub fn uncab_file(cab_file_path: &Path, file_name: Option<&str>, output_dir: Option<&str>) -> Result<PathBuf> {
+    // Ensure the file exists and is a valid file
+    if !cab_file_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", cab_file_path);
+    }
+
+    // Determine the base output directory name
+    let base_name = match file_name {
+        Some(name) => name.to_string(),
+        None => cab_file_path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+
+    // Determine the output directory, numbering it when the name is taken
+    let base_dir = match output_dir {
+        Some(dir) => Path::new(dir),
+        None => cab_file_path.parent().unwrap_or_else(|| Path::new(".")),
+    };
+    let output_dir = extraction_dir(base_dir, &base_name);
+
+    let mut cabinet = read_cabinet(cab_file_path)?;
+    if cabinet.entries.iter().any(|entry| entry.folder >= FOLDER_CONTINUED) {
+        anyhow::bail!("Cabinets spanning several files are not supported");
+    }
+
+    // Create the output directory
+    fs::create_dir_all(&output_dir)?;
+
+    // Decompress every folder once, in the order its files are stored
+    cabinet.entries.sort_by_key(|entry| (entry.folder, entry.offset));
+    let mut reader: Option<(u16, FolderReader, u64)> = None;
+    for entry in &cabinet.entries {
+        let out_path = output_path(&output_dir, &entry.name)?;
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Restart the folder if this file begins before the current position
+        let restart = match &reader {
+            Some((folder, _, position)) => *folder != entry.folder || *position > entry.offset as u64,
+            None => true,
+        };
+        if restart {
+            let folder = cabinet
+                .folders
+                .get(entry.folder as usize)
+                .ok_or_else(|| anyhow::anyhow!("{:?} refers to a missing folder", entry.name))?;
+            let folder_reader = FolderReader::new(cab_file_path, folder, cabinet.data_reserve)?;
+            reader = Some((entry.folder, folder_reader, 0));
+        }
+        let (_, folder_reader, position) = reader.as_mut().unwrap();
+
+        let skip = entry.offset as u64 - *position;
+        if io::copy(&mut folder_reader.take(skip), &mut io::sink())? != skip {
+            anyhow::bail!("Unexpected end of folder data before {:?}", entry.name);
+        }
+        let mut outfile = BufWriter::new(
+            File::create(&out_path).with_context(|| format!("Failed to create file {:?}", out_path))?,
+        );
+        if io::copy(&mut folder_reader.take(entry.size as u64), &mut outfile)? != entry.size as u64 {
+            anyhow::bail!("Unexpected end of folder data in {:?}", entry.name);
+        }
+        outfile.into_inner().map_err(|err| err.into_error())?;
+        *position = entry.offset as u64 + entry.size as u64;
+
+        // Cabinets only record the read-only and execute attributes
+        let mut mode = if entry.attributes & ATTRIBUTE_EXECUTE != 0 { 0o755 } else { 0o644 };
+        if entry.attributes & ATTRIBUTE_READ_ONLY != 0 {
+            mode &= !0o222;
+        }
+        set_modified(&out_path, entry.modified)?;
+        set_mode(&out_path, Some(mode))?;
+    }
+
+    Ok(output_dir)
+}
+
+// Parses the cabinet header, its folders and its files
+fn read_cabinet(cab_file_path: &Path) -> Result<Cabinet> {
+    let cab_file = File::open(cab_file_path)
+        .with_context(|| format!("Failed to open cabinet {:?}", cab_file_path))?;
+    let mut reader = BufReader::new(cab_file);
+
+    let mut header = [0; HEADER_SIZE];
+    reader
+        .read_exact(&mut header)
+        .with_context(|| format!("{:?} is not a cabinet", cab_file_path))?;
+    if &header[..4] != CAB_SIGNATURE {
+        anyhow::bail!("{:?} is not a cabinet", cab_file_path);
+    }
+    let files_offset = u32::from_le_bytes(header[16..20].try_into()?) as u64;
+    let (minor, major) = (header[24], header[25]);
+    if major != 1 {
+        anyhow::bail!("Unsupported cabinet version {}.{}", major, minor);
+    }
+    let folder_count = u16::from_le_bytes([header[26], header[27]]);
+    let file_count = u16::from_le_bytes([header[28], header[29]]);
+    let flags = u16::from_le_bytes([header[30], header[31]]);
+
+    // Optional reserved areas and the names of neighbouring cabinets
+    let (mut folder_reserve, mut data_reserve) = (0, 0);
+    if flags & FLAG_RESERVE_PRESENT != 0 {
+        let header_reserve = read_u16(&mut reader)? as u64;
+        folder_reserve = read_u8(&mut reader)? as u64;
+        data_reserve = read_u8(&mut reader)? as usize;
+        io::copy(&mut (&mut reader).take(header_reserve), &mut io::sink())?;
+    }
+    for flag in [FLAG_PREV_CABINET, FLAG_NEXT_CABINET] {
+        if flags & flag != 0 {
+            read_name(&mut reader)?;
+            read_name(&mut reader)?;
+        }
+    }
+
+    let mut folders = Vec::new();
+    for _ in 0..folder_count {
+        let data_start = read_u32(&mut reader)? as u64;
+        let data_blocks = read_u16(&mut reader)?;
+        let compression = read_u16(&mut reader)?;
+        io::copy(&mut (&mut reader).take(folder_reserve), &mut io::sink())?;
+        folders.push(Folder { data_start, data_blocks, compression });
+    }
+
+    reader.seek(SeekFrom::Start(files_offset))?;
+    let mut entries = Vec::new();
+    for _ in 0..file_count {
+        let size = read_u32(&mut reader)?;
+        let offset = read_u32(&mut reader)?;
+        let folder = read_u16(&mut reader)?;
+        let date = read_u16(&mut reader)?;
+        let time = read_u16(&mut reader)?;
+        let attributes = read_u16(&mut reader)?;
+        let raw_name = read_name(&mut reader)?;
+
+        // Names are UTF-8 when flagged, otherwise treated as Latin-1
+        let name: String = if attributes & ATTRIBUTE_NAME_IS_UTF != 0 {
+            String::from_utf8_lossy(&raw_name).into_owned()
+        } else {
+            raw_name.iter().map(|&byte| byte as char).collect()
+        };
+        let name = name.replace('\\', "/");
+        entries.push(CabEntry {
+            name,
+            size,
+            modified: dos_time(date, time),
+            attributes,
+            folder,
+            offset,
+        });
+    }
+
+    Ok(Cabinet { folders, entries, data_reserve })
+}
+
+impl FolderReader {
+    fn new(cab_file_path: &Path, folder: &Folder, data_reserve: usize) -> Result<Self> {
+        let decoder = match folder.compression & COMPRESSION_MASK {
+            COMPRESSION_NONE => Decoder::Stored,
+            COMPRESSION_MSZIP => Decoder::Mszip(Vec::new()),
+            COMPRESSION_LZX => {
+                let window_bits = ((folder.compression >> 8) & 0x1f) as u32;
+                Decoder::Lzx(Box::new(LzxDecoder::new(window_bits)?))
+            }
+            COMPRESSION_QUANTUM => anyhow::bail!("Quantum compressed cabinets are not supported"),
+            other => anyhow::bail!("Unknown cabinet compression type {}", other),
+        };
+
+        let mut file = BufReader::new(File::open(cab_file_path)?);
+        file.seek(SeekFrom::Start(folder.data_start))?;
+        Ok(FolderReader {
+            file,
+            blocks_left: folder.data_blocks,
+            data_reserve,
+            decoder,
+            buffer: Vec::new(),
+            position: 0,
+        })
+    }
+
+    // Reads, verifies and decompresses the next data block into the buffer
+    fn next_block(&mut self) -> Result<bool> {
+        if self.blocks_left == 0 {
+            return Ok(false);
+        }
+        self.blocks_left -= 1;
+
+        let mut header = [0; 8];
+        self.file.read_exact(&mut header).with_context(|| "Unexpected end of cabinet data")?;
+        let stored_checksum = u32::from_le_bytes(header[..4].try_into()?);
+        let compressed_size = u16::from_le_bytes([header[4], header[5]]) as usize;
+        let size = u16::from_le_bytes([header[6], header[7]]) as usize;
+        if size == 0 {
+            anyhow::bail!("Cabinets spanning several files are not supported");
+        }
+        if size > MAX_BLOCK_SIZE {
+            anyhow::bail!("Cabinet data block is too large");
+        }
+        io::copy(&mut (&mut self.file).take(self.data_reserve as u64), &mut io::sink())?;
+        let mut data = vec![0; compressed_size];
+        self.file.read_exact(&mut data).with_context(|| "Unexpected end of cabinet data")?;
+
+        // A zero checksum means none was computed
+        if stored_checksum != 0 && checksum(&header[4..8], checksum(&data, 0)) != stored_checksum {
+            anyhow::bail!("Cabinet data block checksum mismatch");
+        }
+
+        self.buffer = match &mut self.decoder {
+            Decoder::Stored => data,
+            Decoder::Mszip(window) => inflate_mszip(window, &data, size)?,
+            Decoder::Lzx(decoder) => decoder.decompress_frame(&data, size)?,
+        };
+        if self.buffer.len() != size {
+            anyhow::bail!("Cabinet data block has the wrong uncompressed size");
+        }
+        self.position = 0;
+        Ok(true)
+    }
+}
+
+impl Read for FolderReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.buffer.len() {
+            if !self.next_block().map_err(io::Error::other)? {
+                return Ok(0);
+            }
+        }
+        let count = buf.len().min(self.buffer.len() - self.position);
+        buf[..count].copy_from_slice(&self.buffer[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+// Inflates an MSZIP block, whose deflate stream may refer to the previous
+// block's output. The history is replayed as a stored block ahead of it.
+fn inflate_mszip(window: &mut Vec<u8>, data: &[u8], size: usize) -> Result<Vec<u8>> {
+    let deflated = data
+        .strip_prefix(MSZIP_SIGNATURE)
+        .ok_or_else(|| anyhow::anyhow!("Invalid MSZIP block signature"))?;
+
+    let mut stream = Vec::with_capacity(5 + window.len() + deflated.len());
+    if !window.is_empty() {
+        let length = window.len() as u16;
+        stream.push(0);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(window);
+    }
+    stream.extend_from_slice(deflated);
+
+    let mut output = Vec::new();
+    DeflateDecoder::new(&stream[..])
+        .take((window.len() + size + 1) as u64)
+        .read_to_end(&mut output)?;
+    let output = output.split_off(window.len().min(output.len()));
+
+    // Keep the last 32 KiB of output as the next block's history
+    window.extend_from_slice(&output);
+    let excess = window.len().saturating_sub(MSZIP_WINDOW);
+    window.drain(..excess);
+    Ok(output)
+}
+
+// The cabinet checksum XORs little endian words, with the tail bytes big endian
+fn checksum(data: &[u8], seed: u32) -> u32 {
+    let mut sum = seed;
+    let mut words = data.chunks_exact(4);
+    for word in &mut words {
+        sum ^= u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    let tail = words
+        .remainder()
+        .iter()
+        .fold(0, |tail, &byte| (tail << 8) | byte as u32);
+    sum ^ tail
+}
+
+// Converts an MS-DOS date and local time
+fn dos_time(date: u16, time: u16) -> Option<SystemTime> {
+    #[cfg(unix)]
+    {
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        tm.tm_year = (date >> 9) as i32 + 80;
+        tm.tm_mon = ((date >> 5) & 0x0f) as i32 - 1;
+        tm.tm_mday = (date & 0x1f) as i32;
+        tm.tm_hour = (time >> 11) as i32;
+        tm.tm_min = ((time >> 5) & 0x3f) as i32;
+        tm.tm_sec = (time & 0x1f) as i32 * 2;
+        tm.tm_isdst = -1;
+        if tm.tm_mday == 0 || tm.tm_mon < 0 {
+            return None;
+        }
+        let seconds = unsafe { libc::mktime(&mut tm) };
+        if seconds < 0 {
+            return None;
+        }
+        Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds as u64))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (date, time);
+        None
+    }
+}
+
+// Reads a NUL terminated name
+fn read_name<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut name = Vec::new();
+    loop {
+        let byte = read_u8(reader)?;
+        if byte == 0 {
+            return Ok(name);
+        }
+        if name.len() >= 1024 {
+            anyhow::bail!("Cabinet name is too long");
+        }
+        name.push(byte);
+    }
+}
+
+// Little endian integer readers
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/cab");
+
+    fn text_lines() -> Vec<u8> {
+        (0..1200)
+            .map(|i| format!("line {}: the quick brown fox jumps over the lazy dog\n", i))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    // x86 call instructions, which LZX rewrites to absolute targets
+    fn calls() -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..4000u32 {
+            data.push(0xe8);
+            data.extend_from_slice(&(i * 37 % 9000).to_le_bytes());
+            data.extend_from_slice(&[0x90, 0x90, 0xc3]);
+        }
+        data
+    }
+
+    // Noise that only compresses by matching its earlier repeats
+    fn repeated_noise() -> Vec<u8> {
+        let mut state: u32 = 0x2545f491;
+        let noise: Vec<u8> = (0..12000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        noise.repeat(6)
+    }
+
+    fn extract(fixture: &str) -> (tempfile::TempDir, PathBuf) {
+        let root = tempfile::tempdir().unwrap();
+        let cab = Path::new(FIXTURES).join(fixture);
+        let output = uncab_file(&cab, Some("out"), Some(root.path().to_str().unwrap())).unwrap();
+        (root, output)
+    }
+
+    #[test]
+    fn lists_lzx_cabinet() {
+        let entries = list_cab_file(&Path::new(FIXTURES).join("lzx.cab")).unwrap();
+        let names: Vec<_> = entries.iter().map(|entry| (entry.name.as_str(), entry.size)).collect();
+        assert_eq!(names, [("docs/text.txt", 64890), ("bin/calls.bin", 32000)]);
+    }
+
+    #[test]
+    fn extracts_lzx_folder() {
+        let (_root, output) = extract("lzx.cab");
+        assert_eq!(fs::read(output.join("docs/text.txt")).unwrap(), text_lines());
+        assert_eq!(fs::read(output.join("bin/calls.bin")).unwrap(), calls());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(output.join("bin/calls.bin")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+    }
+
+    #[test]
+    fn numbers_taken_output_inside_output_dir() {
+        let (root, first) = extract("lzx.cab");
+        let cab = Path::new(FIXTURES).join("lzx.cab");
+        let second = uncab_file(&cab, Some("out"), Some(root.path().to_str().unwrap())).unwrap();
+        assert_eq!(first, root.path().join("out"));
+        assert_eq!(second, root.path().join("out-1"));
+        assert_eq!(fs::read(second.join("docs/text.txt")).unwrap(), text_lines());
+    }
+
+    #[test]
+    fn extracts_mszip_history_across_blocks() {
+        let (_root, output) = extract("mszip.cab");
+        assert_eq!(fs::read(output.join("repeat.bin")).unwrap(), repeated_noise());
+        assert_eq!(fs::read(output.join("text.txt")).unwrap(), text_lines());
+    }
+
+    #[test]
+    fn detects_cabinets_by_signature() {
+        assert!(is_cab_file(&Path::new(FIXTURES).join("mszip.cab")));
+        assert!(!is_cab_file(&Path::new(FIXTURES).join("README.md")));
+    }
+}
@@ -0,0 +1,455 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// LZX decompression as used by cabinet folders. Every data block of a folder
+// holds one frame of up to 32 KiB, while the window, the Huffman code lengths
+// and the repeated offsets carry over from one frame to the next.
+use anyhow::{self, Result};
+
+// Block types
+const BLOCK_VERBATIM: u32 = 1;
+const BLOCK_ALIGNED: u32 = 2;
+const BLOCK_UNCOMPRESSED: u32 = 3;
+
+// Tree sizes
+const NUM_CHARS: usize = 256;
+const PRETREE_SIZE: usize = 20;
+const LENGTH_TREE_SIZE: usize = 249;
+const ALIGNED_TREE_SIZE: usize = 8;
+const MAX_MAIN_TREE_SIZE: usize = NUM_CHARS + 50 * 8;
+
+// Pretree runs may overshoot the end of a length table by this much
+const LENGTH_TABLE_SAFETY: usize = 64;
+
+// Matches are between 2 and 257 bytes long
+const NUM_PRIMARY_LENGTHS: usize = 7;
+const MIN_MATCH: usize = 2;
+
+// E8 call translation stops after this many frames
+const MAX_TRANSLATED_FRAMES: u32 = 32768;
+
+// Number of position slots for window sizes of 2^15 to 2^21 bytes
+const POSITION_SLOTS: [usize; 7] = [30, 32, 34, 36, 38, 42, 50];
+
+// Extra offset bits and base offset of every position slot
+const EXTRA_BITS: [u32; 51] = extra_bits();
+const POSITION_BASE: [u32; 51] = position_base();
+
+const fn extra_bits() -> [u32; 51] {
+    let mut bits = [0; 51];
+    let mut slot = 0;
+    let mut extra = 0;
+    while slot < 50 {
+        bits[slot] = extra;
+        bits[slot + 1] = extra;
+        if slot != 0 && extra < 17 {
+            extra += 1;
+        }
+        slot += 2;
+    }
+    bits[50] = extra;
+    bits
+}
+
+const fn position_base() -> [u32; 51] {
+    let bits = extra_bits();
+    let mut base = [0; 51];
+    let mut slot = 0;
+    let mut position = 0;
+    while slot < 51 {
+        base[slot] = position;
+        position += 1 << bits[slot];
+        slot += 1;
+    }
+    base
+}
+
+/// Decoder state of one LZX compressed folder.
+pub struct LzxDecoder {
+    window: Vec<u8>,
+    window_position: usize,
+    total: u64,
+    position_slots: usize,
+    main_lengths: Vec<u8>,
+    length_lengths: Vec<u8>,
+    main_tree: Huffman,
+    length_tree: Huffman,
+    aligned_tree: Huffman,
+    recent: [u32; 3],
+    block_type: u32,
+    block_length: usize,
+    block_remaining: usize,
+    header_read: bool,
+    intel_file_size: i32,
+    intel_started: bool,
+    intel_position: i32,
+    frames: u32,
+    pending: Vec<u8>,
+}
+
+impl LzxDecoder {
+    /// Creates a decoder for a window of 2^`window_bits` bytes.
+    pub fn new(window_bits: u32) -> Result<Self> {
+        if !(15..=21).contains(&window_bits) {
+            anyhow::bail!("Unsupported LZX window size 2^{}", window_bits);
+        }
+        Ok(LzxDecoder {
+            window: vec![0; 1 << window_bits],
+            window_position: 0,
+            total: 0,
+            position_slots: POSITION_SLOTS[window_bits as usize - 15],
+            main_lengths: vec![0; MAX_MAIN_TREE_SIZE + LENGTH_TABLE_SAFETY],
+            length_lengths: vec![0; LENGTH_TREE_SIZE + LENGTH_TABLE_SAFETY],
+            main_tree: Huffman::default(),
+            length_tree: Huffman::default(),
+            aligned_tree: Huffman::default(),
+            recent: [1; 3],
+            block_type: 0,
+            block_length: 0,
+            block_remaining: 0,
+            header_read: false,
+            intel_file_size: 0,
+            intel_started: false,
+            intel_position: 0,
+            frames: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Decodes the frame held by one data block into `frame_size` bytes.
+    pub fn decompress_frame(&mut self, input: &[u8], frame_size: usize) -> Result<Vec<u8>> {
+        let mut reader = BitReader::new(input);
+
+        // The stream starts with the E8 translation header
+        if !self.header_read {
+            if reader.read_bits(1)? == 1 {
+                let high = reader.read_bits(16)?;
+                let low = reader.read_bits(16)?;
+                self.intel_file_size = ((high << 16) | low) as i32;
+            }
+            self.header_read = true;
+        }
+
+        // A match may run past the end of the previous frame into this one
+        while self.pending.len() < frame_size {
+            if self.block_remaining == 0 {
+                self.read_block_header(&mut reader)?;
+                continue;
+            }
+            if self.block_type == BLOCK_UNCOMPRESSED {
+                let count = self.block_remaining.min(frame_size - self.pending.len());
+                for &byte in reader.read_bytes(count)? {
+                    self.push(byte);
+                }
+                self.block_remaining -= count;
+            } else {
+                let length = self.decode_element(&mut reader)?;
+                if length > self.block_remaining {
+                    anyhow::bail!("LZX match runs past the end of its block");
+                }
+                self.block_remaining -= length;
+            }
+        }
+
+        let mut frame: Vec<u8> = self.pending.drain(..frame_size).collect();
+        self.translate(&mut frame);
+        Ok(frame)
+    }
+
+    // Reads a block header and, for compressed blocks, its Huffman trees
+    fn read_block_header(&mut self, reader: &mut BitReader) -> Result<()> {
+        // Uncompressed blocks of odd length are padded to an even size
+        if self.block_type == BLOCK_UNCOMPRESSED && self.block_length % 2 == 1 {
+            reader.read_bytes(1)?;
+        }
+
+        self.block_type = reader.read_bits(3)?;
+        let high = reader.read_bits(16)? as usize;
+        let low = reader.read_bits(8)? as usize;
+        self.block_length = (high << 8) | low;
+        self.block_remaining = self.block_length;
+
+        match self.block_type {
+            BLOCK_VERBATIM | BLOCK_ALIGNED => {
+                if self.block_type == BLOCK_ALIGNED {
+                    let mut lengths = [0; ALIGNED_TREE_SIZE];
+                    for length in lengths.iter_mut() {
+                        *length = reader.read_bits(3)? as u8;
+                    }
+                    self.aligned_tree = Huffman::new(&lengths)?;
+                }
+
+                let main_size = NUM_CHARS + self.position_slots * 8;
+                read_lengths(reader, &mut self.main_lengths, 0, NUM_CHARS)?;
+                read_lengths(reader, &mut self.main_lengths, NUM_CHARS, main_size)?;
+                self.main_tree = Huffman::new(&self.main_lengths[..main_size])?;
+                if self.main_lengths[0xe8] != 0 {
+                    self.intel_started = true;
+                }
+                read_lengths(reader, &mut self.length_lengths, 0, LENGTH_TREE_SIZE)?;
+                self.length_tree = Huffman::new(&self.length_lengths[..LENGTH_TREE_SIZE])?;
+            }
+            BLOCK_UNCOMPRESSED => {
+                // The stored repeated offsets follow the realigned bitstream
+                self.intel_started = true;
+                reader.align();
+                let offsets = reader.read_bytes(12)?;
+                for (recent, bytes) in self.recent.iter_mut().zip(offsets.chunks_exact(4)) {
+                    *recent = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+            }
+            other => anyhow::bail!("Invalid LZX block type {}", other),
+        }
+        Ok(())
+    }
+
+    // Decodes one literal or match, returning the number of bytes it produced
+    fn decode_element(&mut self, reader: &mut BitReader) -> Result<usize> {
+        let element = self.main_tree.decode(reader)? as usize;
+        if element < NUM_CHARS {
+            self.push(element as u8);
+            return Ok(1);
+        }
+
+        let element = element - NUM_CHARS;
+        let mut length = element & NUM_PRIMARY_LENGTHS;
+        if length == NUM_PRIMARY_LENGTHS {
+            length += self.length_tree.decode(reader)? as usize;
+        }
+        length += MIN_MATCH;
+
+        let slot = element >> 3;
+        let offset = match slot {
+            0 => self.recent[0],
+            1 => {
+                self.recent.swap(0, 1);
+                self.recent[0]
+            }
+            2 => {
+                self.recent.swap(0, 2);
+                self.recent[0]
+            }
+            _ => {
+                let extra = EXTRA_BITS[slot];
+                let mut offset = POSITION_BASE[slot] - 2;
+                if self.block_type == BLOCK_ALIGNED && extra >= 3 {
+                    offset += reader.read_bits(extra - 3)? << 3;
+                    offset += self.aligned_tree.decode(reader)? as u32;
+                } else {
+                    offset += reader.read_bits(extra)?;
+                }
+                self.recent = [offset, self.recent[0], self.recent[1]];
+                offset
+            }
+        };
+
+        let offset = offset as usize;
+        if offset == 0 || offset as u64 > self.total || offset > self.window.len() {
+            anyhow::bail!("LZX match offset points outside the window");
+        }
+        let mask = self.window.len() - 1;
+        let mut source = (self.window_position + self.window.len() - offset) & mask;
+        for _ in 0..length {
+            self.push(self.window[source]);
+            source = (source + 1) & mask;
+        }
+        Ok(length)
+    }
+
+    // Appends a decoded byte to the window and the pending output
+    fn push(&mut self, byte: u8) {
+        self.window[self.window_position] = byte;
+        self.window_position = (self.window_position + 1) & (self.window.len() - 1);
+        self.total += 1;
+        self.pending.push(byte);
+    }
+
+    // Undoes the encoder's translation of relative E8 call targets to absolute ones
+    fn translate(&mut self, frame: &mut [u8]) {
+        let translate = self.frames < MAX_TRANSLATED_FRAMES && self.intel_file_size != 0 && self.intel_started;
+        self.frames = self.frames.saturating_add(1);
+        if translate && frame.len() > 10 {
+            let mut index = 0;
+            let mut current = self.intel_position;
+            while index < frame.len() - 10 {
+                if frame[index] != 0xe8 {
+                    index += 1;
+                    current += 1;
+                    continue;
+                }
+                let target = &mut frame[index + 1..index + 5];
+                let absolute = i32::from_le_bytes([target[0], target[1], target[2], target[3]]);
+                if absolute >= -current && absolute < self.intel_file_size {
+                    let relative = if absolute >= 0 { absolute - current } else { absolute + self.intel_file_size };
+                    target.copy_from_slice(&relative.to_le_bytes());
+                }
+                index += 5;
+                current += 5;
+            }
+        }
+        self.intel_position = self.intel_position.wrapping_add(frame.len() as i32);
+    }
+}
+
+// Reads the pretree, then uses it to update code lengths `first..last` in place
+fn read_lengths(reader: &mut BitReader, lengths: &mut [u8], first: usize, last: usize) -> Result<()> {
+    let mut pretree_lengths = [0; PRETREE_SIZE];
+    for length in pretree_lengths.iter_mut() {
+        *length = reader.read_bits(4)? as u8;
+    }
+    let pretree = Huffman::new(&pretree_lengths)?;
+
+    let mut index = first;
+    while index < last {
+        let (run, value) = match pretree.decode(reader)? {
+            17 => (reader.read_bits(4)? as usize + 4, 0),
+            18 => (reader.read_bits(5)? as usize + 20, 0),
+            19 => {
+                let run = reader.read_bits(1)? as usize + 4;
+                let delta = pretree.decode(reader)? as u8;
+                (run, (lengths[index] + 17 - delta) % 17)
+            }
+            delta => (1, (lengths[index] + 17 - delta as u8) % 17),
+        };
+        if index + run > lengths.len() {
+            anyhow::bail!("LZX code lengths run past the end of the table");
+        }
+        lengths[index..index + run].fill(value);
+        index += run;
+    }
+    Ok(())
+}
+
+// Canonical Huffman code decoded with a single lookup table
+#[derive(Default)]
+struct Huffman {
+    table: Vec<(u16, u8)>,
+    bits: u32,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let bits = lengths.iter().copied().max().unwrap_or(0) as u32;
+        if bits == 0 {
+            return Ok(Huffman::default());
+        }
+        if bits > 16 {
+            anyhow::bail!("LZX code length exceeds 16 bits");
+        }
+
+        // Reject over-subscribed codes, then assign codes in canonical order
+        let mut counts = [0u32; 17];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left: i64 = 1;
+        for count in &counts[1..] {
+            left = left * 2 - *count as i64;
+            if left < 0 {
+                anyhow::bail!("Invalid LZX Huffman table");
+            }
+        }
+        let mut next_code = [0u32; 17];
+        for length in 2..=16 {
+            next_code[length] = (next_code[length - 1] + counts[length - 1]) << 1;
+        }
+
+        let mut table = vec![(0, 0); 1 << bits];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+            let start = (code << (bits - length as u32)) as usize;
+            let end = start + (1 << (bits - length as u32));
+            table[start..end].fill((symbol as u16, length));
+        }
+        Ok(Huffman { table, bits })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+        if self.bits == 0 {
+            anyhow::bail!("LZX data uses an empty Huffman table");
+        }
+        let (symbol, length) = self.table[reader.peek_bits(self.bits) as usize];
+        if length == 0 {
+            anyhow::bail!("Invalid LZX Huffman code");
+        }
+        reader.consume(length as u32);
+        Ok(symbol)
+    }
+}
+
+// Reads bits most significant first from little endian 16 bit words
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, position: 0, buffer: 0, count: 0 }
+    }
+
+    // Tops the buffer up to at least 17 bits; past the end of the data it reads zeros
+    fn fill(&mut self) {
+        while self.count <= 16 {
+            let word = match self.data.get(self.position..self.position + 2) {
+                Some(bytes) => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+                None => 0,
+            };
+            self.buffer |= word << (16 - self.count);
+            self.count += 16;
+            self.position += 2;
+        }
+    }
+
+    fn peek_bits(&mut self, bits: u32) -> u32 {
+        self.fill();
+        self.buffer >> (32 - bits)
+    }
+
+    fn consume(&mut self, bits: u32) {
+        self.buffer <<= bits;
+        self.count -= bits;
+    }
+
+    fn read_bits(&mut self, bits: u32) -> Result<u32> {
+        if bits == 0 {
+            return Ok(0);
+        }
+        if bits > 16 {
+            let high = self.read_bits(bits - 16)?;
+            return Ok((high << 16) | self.read_bits(16)?);
+        }
+        if self.position > self.data.len() + 4 {
+            anyhow::bail!("Unexpected end of LZX data");
+        }
+        let value = self.peek_bits(bits);
+        self.consume(bits);
+        Ok(value)
+    }
+
+    // Skips to the next word boundary, skipping a whole word if already aligned
+    fn align(&mut self) {
+        self.position -= 2 * (self.count / 16) as usize;
+        if self.count.is_multiple_of(16) {
+            self.position += 2;
+        }
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    // Reads raw bytes; only valid while the bit buffer is empty
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected end of LZX data"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+}
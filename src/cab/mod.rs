@@ -0,0 +1,4 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+pub mod cab_utils;
+pub mod lzx;
\ No newline at end of file
@@ -34,21 +34,184 @@ MODES:
                         options listed below in conjunction
                         with this flag.
 
-    --unzip,    -uz     Unzip a folder. Only the 
+    --unzip,    -uz     Unzip a folder. Microsoft cabinets (.cab) are
+                        extracted as well. Only the 
                         --name and --output options are supported.
 
+    --zipalign, -za     Rewrite a zip so stored entries start on an
+                        alignment boundary, padding local headers with
+                        an extra field. Only the --name, --align, and
+                        --output options are supported.
+
+    --checkalign, -ca   Report stored zip entries that are not aligned.
+                        Only the --align option is supported.
+
+    --package,  -pkg    Zip an EPUB, OpenDocument or OOXML document
+                        folder with the mimetype entry first and
+                        stored. The type is detected unless --format
+                        is given. Only the --name, --format, --level,
+                        and --output options are supported.
+
     --tar,      -t      Create a tar archive of a folder. Only
-                        the --name and --output options are supported.
+                        the --name, --compression, --level, and
+                        --output options are supported.
+
+    --untar,    -ut     Extract a tar archive. Compressed archives
+                        are detected automatically. Only the
+                        --name and --output options are supported.
+
+    --gzip,     -gz     Compress a folder with gzip, storing the file
+                        name and modification time. Only the --name,
+                        --level, --comment, --no-name, and --output
+                        options are supported.
+
+    --ungzip,   -ugz    Decompress a gzip archive, including files made
+                        of several concatenated members, restoring the
+                        stored name and modification time. Only the
+                        --name, --members, --no-name, and --output
+                        options are supported.
+
+    --zstd,     -zst    Compress a file with zstd. Only the
+                        --name, --level, and --output options are supported.
+
+    --unzstd,   -uzst   Decompress a zstd file. Only the
+                        --name and --output options are supported.
+
+    --xz,       -xz     Compress a file with xz. Only the --name,
+                        --level, --extreme, and --output options
+                        are supported.
 
-    --untar,    -ut     Extract a tar archive. Only the 
+    --lzma,     -lzma   Compress a file with the legacy lzma format.
+                        Only the --name, --level, --extreme, and
+                        --output options are supported.
+
+    --unxz,     -uxz    Decompress an xz or lzma file. Only the
                         --name and --output options are supported.
 
-    --gzip,     -gz     Compress a folder with gzip. Only the
+    --bzip2,    -bz2    Compress a file with bzip2. Only the
                         --name, --level, and --output options are supported.
 
-    --ungzip,   -ugz    Decompress a gzip archive. Only the
+    --bunzip2,  -ubz2   Decompress a bzip2 file. Only the
+                        --name and --output options are supported.
+
+    --lz4,      -lz4    Compress a file with the lz4 frame format.
+                        Only the --name, --level, and --output
+                        options are supported.
+
+    --unlz4,    -ulz4   Decompress an lz4 file. Only the
+                        --name and --output options are supported.
+
+    --brotli,   -br     Compress a file with brotli. When given a
+                        folder, every file inside it that is not
+                        already compressed is written to a sibling
+                        .br file, or under --output when given.
+                        Only the --name, --level, --window, and
+                        --output options are supported.
+
+    --unbrotli, -ubr    Decompress a brotli file. Only the
+                        --name and --output options are supported.
+
+    --lzip,     -lz     Compress a file with lzip. Only the
+                        --name, --level, and --output options are supported.
+
+    --unlzip,   -ulz    Decompress an lzip file, verifying each
+                        member's trailer. Only the --name and
+                        --output options are supported.
+
+    --uncompress, -uZ   Decompress a legacy Unix compress (.Z) file.
+                        Only the --name and --output options are
+                        supported.
+
+    --zlib,     -zl     Compress a file into a zlib stream. Only the
+                        --name, --level, and --output options are supported.
+
+    --unzlib,   -uzl    Decompress a zlib stream, verifying its
+                        Adler-32 checksum. Only the --name and
+                        --output options are supported.
+
+    --deflate,  -dfl    Compress a file into a raw deflate stream.
+                        Only the --name, --level, and --output
+                        options are supported.
+
+    --inflate,  -ifl    Decompress a raw deflate stream. Only the
+                        --name and --output options are supported.
+
+    --snappy,   -sz     Compress a file with the snappy framing format.
+                        Only the --name and --output options are supported.
+
+    --unsnappy, -usz    Decompress a snappy framed file, verifying the
+                        CRC32C of every chunk. Only the --name and
+                        --output options are supported.
+
+    --7z,       -7z     Compress a folder into a 7z archive with
+                        LZMA2. Only the --name, --level, --dictionary,
+                        --solid, --password, --encrypt-headers, and
+                        --output options are supported.
+
+    --un7z,     -u7z    Extract a 7z archive (LZMA, LZMA2, solid
+                        blocks, BCJ filters, compressed headers).
+                        Only the --name, --password, and --output
+                        options are supported.
+
+    --list7z,   -ls7z   List the entries of a 7z archive. Only the
+                        --password option is supported.
+
+    --cpio,     -cpio   Archive a folder with cpio. Only the --name,
+                        --format, and --output options are supported.
+
+    --uncpio,   -ucpio  Extract a cpio archive. Compressed archives
+                        are detected automatically. Only the --name
+                        and --output options are supported.
+
+    --ar,       -ar     Archive the files of a folder with ar. Only
+                        the --name, --format, and --output options
+                        are supported.
+
+    --unar,     -uar    Extract an ar archive. Only the --name and
+                        --output options are supported.
+
+    --listar,   -lsar   List the members of an ar archive.
+
+    --undeb,    -udeb   Print the control metadata of a Debian package
+                        and extract its data payload. Only the --name
+                        and --output options are supported.
+
+    --unrpm,    -urpm   Print the name, version and architecture of an
+                        rpm package and extract its payload. Only the
                         --name and --output options are supported.
 
+    --uniso,    -uiso   Extract an ISO 9660 image, using Rock Ridge or
+                        Joliet names when present. Only the --name and
+                        --output options are supported.
+
+    --listiso,  -lsiso  List the files and folders of an ISO 9660 image.
+
+    --unsquashfs, -usqfs
+                        Extract a SquashFS 4.0 image (gzip, xz, lzma,
+                        lz4 or zstd), restoring permissions, owners,
+                        symlinks and hard links. Only the --name and
+                        --output options are supported.
+
+    --listsquashfs, -lssqfs
+                        List the contents of a SquashFS image.
+
+    --uncab,    -ucab   Extract a Microsoft cabinet (.cab) with stored,
+                        MSZIP or LZX folders. Only the --name and
+                        --output options are supported.
+
+    --listcab,  -lscab  List the files of a cabinet.
+
+    --oci-layer, -oci   Build a reproducible OCI image layer from a
+                        folder and print its diff_id and digest. Only
+                        the --name, --compression (none, gzip, zstd),
+                        --level, --base, and --output options are
+                        supported.
+
+    --undocker, -udkr   Flatten a `docker save` tarball into a root
+                        filesystem, applying its layers in order along
+                        with their whiteouts. Only the --name and
+                        --output options are supported.
+
 OPTIONS:
     --name,     -n      Specify the name of the output file or folder.
                         This is optional and defaults to the name of
@@ -62,16 +225,93 @@ OPTIONS:
                         - Deflate: 0-9 (default: 6)
                         - Bzip2:   0-9 (default: 6)
                         - Zstd:   -7-22 (0 maps to level 0, default: 3)
+                        - Xz:      0-9 (default: 6)
+                        - Lzip:    0-9 (default: 6)
+                        - Lz4:     0-12 (3 and above use HC, default: 0)
+                        - Brotli:  0-11 (default: 11)
+                        - 7z:      0-9 (default: 6)
+
+    --align,    -a      Specify the zip alignment in bytes for stored
+                        entries. Optional; 1-65535 (default: 4096 for
+                        --zipalign and --checkalign). With --zip it
+                        requires the stored compression method.
+
+    --comment           Store a comment in the gzip header.
+
+    --no-name           Leave the file name and modification time out
+                        of the gzip header, or ignore them when
+                        decompressing.
+
+    --members,  -m      List the members of a gzip file with their
+                        offsets and sizes while decompressing it.
+
+    --window,   -w      Specify the brotli window size as a power
+                        of two. Optional; 10-24 (default: 22).
+
+    --dictionary, -d    Specify the 7z LZMA2 dictionary size, such as
+                        64m. Optional; 4k-1536m (default: set by level).
+
+    --solid,    -s      Specify the 7z solid block size, such as 64m,
+                        or on/off. Optional; defaults to one solid
+                        block for the whole archive.
+
+    --format,   -f      Specify the archive format. Optional.
+                        - cpio: newc (default), odc, or crc
+                        - ar:   gnu (default) or bsd
+                        - package: epub, odf, or ooxml (default:
+                          detected from the folder)
+
+    --base,     -b      Specify the folder an OCI layer is built on.
+                        Only changes against it are stored, and
+                        deleted paths become whiteout files.
+
+    --password, -p      Encrypt or decrypt 7z contents with AES-256.
+
+    --encrypt-headers, -eh
+                        Also encrypt the 7z headers, hiding file
+                        names. Requires --password.
+
+    --extreme,  -e      Use the slower extreme preset.
+                        Optional; only applicable to xz and lzma.
 
-    --compression, -c   Specify the compression algorithm (zip only).
-                        Optional. Supported values:
+    --compression, -c   Specify the compression algorithm.
+                        Optional. Supported values for zip:
                         - deflate (default)
                         - bzip2 (bzip)
                         - zstd (z)
+                        - stored (none)
+                        Supported values for tar:
+                        - none (default)
+                        - gzip (gz)
+                        - zstd (zst)
+                        - xz
+                        - bzip2 (bz2)
+                        - lz4
+                        - lzip (lz)
 
 EXAMPLES:
     fecr --unzip /path/to/archive.zip -n output_folder
     fecr --zip /path/to/folder -n output.zip -c bzip2 -l 9
+    fecr --package /path/to/book -f epub -l 9
+    fecr --zip /path/to/assets -c stored -a 4096
+    fecr --zipalign /path/to/app.apk -a 4096
     fecr --tar /path/to/folder -n output.tar -o /path/to/output
+    fecr --tar /path/to/folder -c gzip -l 9
+    fecr --zstd /path/to/file -l 19
+    fecr --xz /path/to/file -l 9 -e
+    fecr --tar /path/to/folder -c bzip2 -o /path/to/output
+    fecr --lz4 /path/to/file -l 9
+    fecr --brotli /path/to/site -l 11 -w 24
+    fecr --7z /path/to/folder -l 9 -d 64m -s 256m -p secret -eh
+    fecr --un7z /path/to/archive.7z -o /path/to/output
+    fecr --cpio /path/to/rootfs -f newc -n initramfs
+    fecr --ar /path/to/objects -f bsd -n libfoo
+    fecr --undeb /path/to/package.deb -o /path/to/output
+    fecr --unrpm /path/to/package.rpm -o /path/to/output
+    fecr --uniso /path/to/image.iso -o /path/to/output
+    fecr --unsquashfs /path/to/rootfs.squashfs -n rootfs
+    fecr --uncab /path/to/driver.cab -o /path/to/output
+    fecr --oci-layer /path/to/rootfs -c gzip -b /path/to/base
+    fecr --undocker /path/to/image.tar -o /path/to/rootfs
     fecr --license
 "#;
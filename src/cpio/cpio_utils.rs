@@ -0,0 +1,680 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use walkdir::WalkDir;
+use anyhow::{self, Result, Context};
+use crate::archive::archive_utils::{make_node, output_path, set_mode, set_modified, set_owner};
+use crate::tar::tar_utils::decompressing_reader;
+
+// Header magic of each supported cpio variant
+const NEWC_MAGIC: &[u8] = b"070701";
+const CRC_MAGIC: &[u8] = b"070702";
+const ODC_MAGIC: &[u8] = b"070707";
+const TRAILER: &str = "TRAILER!!!";
+
+// Longest member name accepted, including its NUL, matching Linux's PATH_MAX
+const MAX_NAME_SIZE: u64 = 4096;
+
+// File type bits of a Unix st_mode
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFDIR: u32 = 0o040000;
+
+// Suffixes stripped from an archive name to build the default output folder name
+const CPIO_SUFFIXES: &[&str] = &[".cpio.gz", ".cpio.zst", ".cpio.xz", ".cpio.bz2", ".cpio.lz4", ".cpio.lz", ".cpio"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Newc,
+    Crc,
+    Odc,
+}
+
+// Metadata of a single archive member
+struct Header {
+    format: Format,
+    dev: (u32, u32),
+    ino: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    nlink: u32,
+    mtime: u64,
+    file_size: u64,
+    rdev: (u32, u32),
+    check: u32,
+    name: String,
+}
+
+pub fn cpio_folder(
+    folder_dir: &Path,
+    file_name: Option<&str>,
+    format: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    // Ensure the folder exists
+    if !folder_dir.is_dir() {
+        anyhow::bail!("The provided folder path does not exist or is not a directory.");
+    }
+
+    let format = match format {
+        None | Some("newc") => Format::Newc,
+        Some("crc") => Format::Crc,
+        Some("odc") => Format::Odc,
+        Some(invalid) => {
+            anyhow::bail!("Invalid cpio format: '{}'", invalid);
+        }
+    };
+
+    // Get the folder's name to use as the base file name
+    let folder_name = folder_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine folder name"))?;
+    let base_name = file_name.unwrap_or(folder_name);
+
+    // Determine the output path
+    let output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => folder_dir.parent().unwrap_or_else(|| Path::new(".")).to_path_buf(),
+    };
+
+    // Resolve conflicts with existing file names by appending a number
+    let mut full_output_path = output_path.join(format!("{}.cpio", base_name));
+    let mut counter = 1;
+    while full_output_path.exists() {
+        full_output_path = output_path.join(format!("{}-{}.cpio", base_name, counter));
+        counter += 1;
+    }
+
+    // Create the cpio archive
+    let cpio_file = File::create(&full_output_path)
+        .with_context(|| format!("Failed to create cpio file at {:?}", full_output_path))?;
+    write_cpio_stream(folder_dir, BufWriter::new(cpio_file), format)?
+        .flush()
+        .with_context(|| "Failed to finalize the cpio archive")?;
+
+    Ok(full_output_path)
+}
+
+// Writes the contents of `folder_dir` as a cpio stream into `writer`
+fn write_cpio_stream<W: Write>(folder_dir: &Path, mut writer: W, format: Format) -> Result<W> {
+    let mut written = 0;
+    let mut ino = 0;
+
+    for entry in WalkDir::new(folder_dir).min_depth(1) {
+        let entry = entry.with_context(|| format!("Failed to read directory {:?}", folder_dir))?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        let name = path
+            .strip_prefix(folder_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        ino += 1;
+        let mut header = Header::from_metadata(format, ino, name, &metadata)?;
+
+        // Symlinks store their target as the member data
+        if header.mode & S_IFMT == S_IFLNK {
+            let target = fs::read_link(path)?;
+            let target = target.to_string_lossy();
+            header.file_size = target.len() as u64;
+            header.check = checksum(target.as_bytes());
+            written += header.write(&mut writer)?;
+            writer.write_all(target.as_bytes())?;
+            written += target.len() as u64;
+        } else if header.mode & S_IFMT == S_IFREG {
+            let mut file = File::open(path)
+                .with_context(|| format!("Failed to open file {:?}", path))?;
+
+            // The crc variant needs the byte sum up front, so read the file twice
+            if format == Format::Crc {
+                let mut summer = Checksum(0);
+                io::copy(&mut file, &mut summer)?;
+                header.check = summer.0;
+                file.rewind()?;
+            }
+            written += header.write(&mut writer)?;
+            let copied = io::copy(&mut file.take(header.file_size), &mut writer)?;
+            if copied != header.file_size {
+                anyhow::bail!("File {:?} changed size while being archived", path);
+            }
+            written += copied;
+        } else {
+            header.file_size = 0;
+            written += header.write(&mut writer)?;
+        }
+        written += write_padding(&mut writer, format, written)?;
+    }
+
+    // Finish with the trailer and pad the archive to whole 512 byte blocks
+    let trailer = Header {
+        nlink: 1,
+        ..Header::empty(format, TRAILER.to_string())
+    };
+    written += trailer.write(&mut writer)?;
+    written += write_padding(&mut writer, format, written)?;
+    let block_padding = (512 - written % 512) % 512;
+    writer.write_all(&vec![0; block_padding as usize])?;
+
+    Ok(writer)
+}
+
+// Pads the newc and crc variants to the next multiple of four bytes
+fn write_padding<W: Write>(writer: &mut W, format: Format, written: u64) -> Result<u64> {
+    if format == Format::Odc {
+        return Ok(0);
+    }
+    let padding = (4 - written % 4) % 4;
+    writer.write_all(&vec![0; padding as usize])?;
+    Ok(padding)
+}
+
+pub fn uncpio_file(
+    cpio_file_path: &Path,
+    file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    // Ensure the input cpio file exists
+    if !cpio_file_path.is_file() {
+        anyhow::bail!("The provided cpio file path does not exist or is not a file.");
+    }
+
+    // Get the cpio file's name to use as the base name if no name is provided
+    let cpio_file_name = cpio_file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine cpio file name"))?;
+
+    let base_name = file_name.unwrap_or_else(|| {
+        CPIO_SUFFIXES
+            .iter()
+            .find_map(|suffix| cpio_file_name.strip_suffix(suffix))
+            .unwrap_or(cpio_file_name)
+    });
+
+    // Determine the base output directory
+    let base_output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => cpio_file_path.parent().unwrap_or_else(|| Path::new(".")).join(format!("{}_extracted", base_name)),
+    };
+
+    // Resolve conflicts by appending a number to the output directory
+    let mut output_path = base_output_path.clone();
+    let mut counter = 1;
+    while output_path.exists() {
+        output_path = base_output_path.with_file_name(format!(
+            "{}-{}",
+            base_output_path.file_name().unwrap().to_str().unwrap(),
+            counter
+        ));
+        counter += 1;
+    }
+
+    // Create the output directory
+    fs::create_dir_all(&output_path)
+        .with_context(|| format!("Failed to create output directory {:?}", output_path))?;
+
+    // Open the cpio file, transparently decompressing it if needed
+    let cpio_file = File::open(cpio_file_path)
+        .with_context(|| format!("Failed to open cpio file {:?}", cpio_file_path))?;
+    let reader = decompressing_reader(BufReader::new(cpio_file))
+        .with_context(|| format!("Failed to read cpio file {:?}", cpio_file_path))?;
+
+    unpack_cpio_stream(reader, &output_path)?;
+
+    Ok(output_path)
+}
+
+/// Unpacks a newc, crc or odc cpio stream into `output_dir`, restoring
+/// modes, mtimes, symlinks and hard links, and ownership and device nodes
+/// where the current user is permitted to.
+pub fn unpack_cpio_stream<R: Read>(mut reader: R, output_dir: &Path) -> Result<()> {
+    let mut directories = Vec::new();
+    let mut links: HashMap<((u32, u32), u64), PathBuf> = HashMap::new();
+    let mut pending_links: HashMap<((u32, u32), u64), Vec<PathBuf>> = HashMap::new();
+
+    loop {
+        let header = Header::read(&mut reader)?;
+        if header.name == TRAILER {
+            break;
+        }
+
+        let out_path = output_path(output_dir, &header.name)?;
+        let mut data = (&mut reader).take(header.file_size);
+        let modified = Some(UNIX_EPOCH + Duration::from_secs(header.mtime));
+
+        match header.mode & S_IFMT {
+            S_IFDIR => {
+                fs::create_dir_all(&out_path)
+                    .with_context(|| format!("Failed to create directory {:?}", out_path))?;
+                if out_path != output_dir {
+                    directories.push((out_path, header.mode, header.uid, header.gid, modified));
+                }
+            }
+            _ if out_path == output_dir => {
+                anyhow::bail!("Archive member {:?} has an empty name", header.name);
+            }
+            S_IFLNK => {
+                create_parent(&out_path)?;
+                let mut target = String::new();
+                data.read_to_string(&mut target)?;
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(&target, &out_path)
+                    .with_context(|| format!("Failed to create symlink {:?}", out_path))?;
+                set_owner(&out_path, header.uid, header.gid)?;
+            }
+            S_IFREG => {
+                create_parent(&out_path)?;
+                let key = (header.dev, header.ino);
+                let linked = header.nlink > 1;
+
+                // Once a linked file has its data, later members only link to
+                // it. newc stores the data once, on the last member, while odc
+                // stores it on the first and may repeat it on every member.
+                if let Some(existing) = links.get(&key).filter(|_| linked) {
+                    fs::hard_link(existing, &out_path)
+                        .with_context(|| format!("Failed to create hard link {:?}", out_path))?;
+                    io::copy(&mut data, &mut io::sink())?;
+                    skip_padding(&mut reader, &header)?;
+                    continue;
+                }
+                if linked && header.file_size == 0 {
+                    pending_links.entry(key).or_default().push(out_path.clone());
+                }
+
+                let mut outfile = BufWriter::new(
+                    File::create(&out_path)
+                        .with_context(|| format!("Failed to create file {:?}", out_path))?,
+                );
+                let mut summer = Checksum(0);
+                io::copy(&mut data, &mut TeeWriter(&mut outfile, &mut summer))?;
+                outfile.into_inner().map_err(|err| err.into_error())?;
+                if header.format == Format::Crc && summer.0 != header.check {
+                    anyhow::bail!("cpio checksum mismatch for {:?}", header.name);
+                }
+                set_modified(&out_path, modified)?;
+                set_owner(&out_path, header.uid, header.gid)?;
+                set_mode(&out_path, Some(header.mode))?;
+
+                if linked && header.file_size > 0 {
+                    for pending in pending_links.remove(&key).unwrap_or_default() {
+                        fs::remove_file(&pending)?;
+                        fs::hard_link(&out_path, &pending)
+                            .with_context(|| format!("Failed to create hard link {:?}", pending))?;
+                    }
+                    links.insert(key, out_path);
+                }
+            }
+            _ => {
+                // Device nodes, FIFOs and sockets are skipped without privileges
+                create_parent(&out_path)?;
+                if make_node(&out_path, header.mode, header.rdev.0, header.rdev.1)? {
+                    set_owner(&out_path, header.uid, header.gid)?;
+                    set_mode(&out_path, Some(header.mode))?;
+                }
+            }
+        }
+
+        // Skip whatever the member did not consume, then the alignment padding
+        io::copy(&mut data, &mut io::sink())?;
+        skip_padding(&mut reader, &header)?;
+    }
+
+    // Links to an empty file never receive data, so join them to each other
+    for paths in pending_links.values() {
+        if let Some((first, rest)) = paths.split_first() {
+            for path in rest {
+                fs::remove_file(path)?;
+                fs::hard_link(first, path)
+                    .with_context(|| format!("Failed to create hard link {:?}", path))?;
+            }
+        }
+    }
+
+    // Restore directory metadata last, as extracting their contents bumps the
+    // timestamps and a read-only mode would block writing them
+    for (path, mode, uid, gid, modified) in directories.iter().rev() {
+        set_modified(path, *modified)?;
+        set_owner(path, *uid, *gid)?;
+        set_mode(path, Some(*mode))?;
+    }
+
+    Ok(())
+}
+
+// Skips the padding that aligns newc and crc member data to four bytes
+fn skip_padding<R: Read>(reader: &mut R, header: &Header) -> Result<()> {
+    if header.format != Format::Odc {
+        let padding = (4 - header.file_size % 4) % 4;
+        io::copy(&mut reader.take(padding), &mut io::sink())?;
+    }
+    Ok(())
+}
+
+// Creates the parent directories of an extracted member
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    Ok(())
+}
+
+impl Header {
+    fn empty(format: Format, name: String) -> Self {
+        Header {
+            format,
+            dev: (0, 0),
+            ino: 0,
+            mode: 0,
+            uid: 0,
+            gid: 0,
+            nlink: 0,
+            mtime: 0,
+            file_size: 0,
+            rdev: (0, 0),
+            check: 0,
+            name,
+        }
+    }
+
+    // Builds a header from the metadata of a file on disk
+    fn from_metadata(format: Format, ino: u64, name: String, metadata: &fs::Metadata) -> Result<Self> {
+        let mut header = Header::empty(format, name);
+        header.ino = ino;
+        header.nlink = if metadata.is_dir() { 2 } else { 1 };
+        header.file_size = metadata.len();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            header.mode = metadata.mode();
+            header.uid = metadata.uid();
+            header.gid = metadata.gid();
+            header.mtime = metadata.mtime().max(0) as u64;
+            let rdev = metadata.rdev();
+            header.rdev = (libc::major(rdev as _) as u32, libc::minor(rdev as _) as u32);
+        }
+        #[cfg(not(unix))]
+        {
+            header.mode = if metadata.is_dir() { S_IFDIR | 0o755 } else { S_IFREG | 0o644 };
+            header.mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs());
+        }
+        Ok(header)
+    }
+
+    // Writes the header and name, returning the number of bytes written
+    fn write<W: Write>(&self, writer: &mut W) -> Result<u64> {
+        let name_size = self.name.len() as u64 + 1;
+        let header = match self.format {
+            Format::Newc | Format::Crc => {
+                let magic = if self.format == Format::Crc { CRC_MAGIC } else { NEWC_MAGIC };
+                let fields = [
+                    self.ino,
+                    self.mode as u64,
+                    self.uid as u64,
+                    self.gid as u64,
+                    self.nlink as u64,
+                    self.mtime,
+                    self.file_size,
+                    self.dev.0 as u64,
+                    self.dev.1 as u64,
+                    self.rdev.0 as u64,
+                    self.rdev.1 as u64,
+                    name_size,
+                    self.check as u64,
+                ];
+                let mut header = String::from_utf8_lossy(magic).into_owned();
+                for value in fields {
+                    if value > u32::MAX as u64 {
+                        anyhow::bail!("{:?} does not fit in a newc header", self.name);
+                    }
+                    header.push_str(&format!("{:08x}", value));
+                }
+                header
+            }
+            Format::Odc => {
+                let rdev = ((self.rdev.0 as u64) << 8) | self.rdev.1 as u64;
+                let fields = [
+                    (0, 6),
+                    (self.ino, 6),
+                    (self.mode as u64, 6),
+                    (self.uid as u64, 6),
+                    (self.gid as u64, 6),
+                    (self.nlink as u64, 6),
+                    (rdev, 6),
+                    (self.mtime, 11),
+                    (name_size, 6),
+                    (self.file_size, 11),
+                ];
+                let mut header = String::from_utf8_lossy(ODC_MAGIC).into_owned();
+                for (value, width) in fields {
+                    if value >> (3 * width) != 0 {
+                        anyhow::bail!("{:?} does not fit in an odc header", self.name);
+                    }
+                    header.push_str(&format!("{:0width$o}", value, width = width));
+                }
+                header
+            }
+        };
+
+        // The name is NUL terminated and, for newc and crc, padded so the
+        // data starts on a four byte boundary
+        let written = header.len() as u64 + name_size;
+        writer.write_all(header.as_bytes())?;
+        writer.write_all(self.name.as_bytes())?;
+        writer.write_all(&[0])?;
+        Ok(written + write_padding(writer, self.format, written)?)
+    }
+
+    // Reads the next header and name, leaving the reader at the member data
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0; 6];
+        reader
+            .read_exact(&mut magic)
+            .with_context(|| "Unexpected end of cpio archive")?;
+
+        let (mut header, name_size) = match &magic[..] {
+            NEWC_MAGIC | CRC_MAGIC => {
+                let format = if &magic[..] == CRC_MAGIC { Format::Crc } else { Format::Newc };
+                let mut fields = [0; 104];
+                reader.read_exact(&mut fields)?;
+                let field = |index: usize| parse_field(&fields[index * 8..index * 8 + 8], 16);
+                let mut header = Header::empty(format, String::new());
+                header.ino = field(0)?;
+                header.mode = field(1)? as u32;
+                header.uid = field(2)? as u32;
+                header.gid = field(3)? as u32;
+                header.nlink = field(4)? as u32;
+                header.mtime = field(5)?;
+                header.file_size = field(6)?;
+                header.dev = (field(7)? as u32, field(8)? as u32);
+                header.rdev = (field(9)? as u32, field(10)? as u32);
+                header.check = field(12)? as u32;
+                (header, field(11)?)
+            }
+            ODC_MAGIC => {
+                let mut fields = [0; 70];
+                reader.read_exact(&mut fields)?;
+                let field = |start: usize, len: usize| parse_field(&fields[start..start + len], 8);
+                let mut header = Header::empty(Format::Odc, String::new());
+                header.dev = (field(0, 6)? as u32, 0);
+                header.ino = field(6, 6)?;
+                header.mode = field(12, 6)? as u32;
+                header.uid = field(18, 6)? as u32;
+                header.gid = field(24, 6)? as u32;
+                header.nlink = field(30, 6)? as u32;
+                let rdev = field(36, 6)?;
+                header.rdev = ((rdev >> 8) as u32, (rdev & 0xff) as u32);
+                header.mtime = field(42, 11)?;
+                header.file_size = field(59, 11)?;
+                (header, field(53, 6)?)
+            }
+            _ => anyhow::bail!("Unsupported cpio header (expected newc, crc or odc)"),
+        };
+
+        // The size comes from the archive, so check it before allocating
+        if name_size > MAX_NAME_SIZE {
+            anyhow::bail!("Member name of {} bytes is too long", name_size);
+        }
+
+        // The name is NUL terminated and, for newc and crc, padded so the
+        // data starts on a four byte boundary
+        let padding = match header.format {
+            Format::Odc => 0,
+            _ => (4 - (110 + name_size) % 4) % 4,
+        };
+        let mut name = vec![0; (name_size + padding) as usize];
+        reader.read_exact(&mut name)?;
+        name.truncate(name_size as usize);
+        if name.pop() != Some(0) {
+            anyhow::bail!("Malformed cpio member name");
+        }
+        header.name = String::from_utf8_lossy(&name).into_owned();
+
+        Ok(header)
+    }
+}
+
+// Parses a fixed width hexadecimal or octal header field
+fn parse_field(field: &[u8], radix: u32) -> Result<u64> {
+    std::str::from_utf8(field)
+        .ok()
+        .and_then(|text| u64::from_str_radix(text, radix).ok())
+        .ok_or_else(|| anyhow::anyhow!("Malformed cpio header field: {:?}", String::from_utf8_lossy(field)))
+}
+
+// Sum of all data bytes, as stored by the crc variant
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |sum, byte| sum.wrapping_add(*byte as u32))
+}
+
+// Accumulates the crc variant's checksum of everything written to it
+struct Checksum(u32);
+
+impl Write for Checksum {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 = self.0.wrapping_add(checksum(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Writes the same bytes to two writers
+struct TeeWriter<'a, A: Write, B: Write>(&'a mut A, &'a mut B);
+
+impl<A: Write, B: Write> Write for TeeWriter<'_, A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.0.write(buf)?;
+        self.1.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/cpio");
+
+    fn extract_fixture(fixture: &str) -> (tempfile::TempDir, PathBuf) {
+        let root = tempfile::tempdir().unwrap();
+        let output = root.path().join("out");
+        let archive = File::open(Path::new(FIXTURES).join(fixture)).unwrap();
+        unpack_cpio_stream(BufReader::new(archive), &output).unwrap();
+        (root, output)
+    }
+
+    #[cfg(unix)]
+    fn assert_linked(output: &Path) {
+        use std::os::unix::fs::MetadataExt;
+        let inode = |name: &str| fs::metadata(output.join(name)).unwrap().ino();
+        assert_eq!(inode("dir/one"), inode("dir/three"));
+        assert_eq!(inode("dir/one"), inode("two"));
+        assert_eq!(fs::metadata(output.join("two")).unwrap().nlink(), 3);
+        assert_eq!(fs::read_to_string(output.join("two")).unwrap(), "shared data\n");
+        assert_eq!(fs::read_to_string(output.join("solo")).unwrap(), "solo\n");
+        assert_eq!(fs::read_link(output.join("link")).unwrap(), Path::new("solo"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relinks_odc_hard_links() {
+        let (_root, output) = extract_fixture("links.odc");
+        assert_linked(&output);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relinks_newc_hard_links() {
+        let (_root, output) = extract_fixture("links.newc");
+        assert_linked(&output);
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = root.path().join("tree");
+        fs::create_dir_all(folder.join("sub/empty")).unwrap();
+        fs::write(folder.join("a.txt"), "hello\n").unwrap();
+        fs::write(folder.join("sub/odd"), "three").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("a.txt", folder.join("link")).unwrap();
+
+        let output = root.path().to_str().unwrap();
+        for format in ["newc", "crc", "odc"] {
+            let archive = cpio_folder(&folder, Some(format), Some(format), Some(output)).unwrap();
+            let extracted = uncpio_file(&archive, None, None).unwrap();
+            assert_eq!(extracted, root.path().join(format!("{}_extracted", format)));
+            assert_eq!(fs::read_to_string(extracted.join("a.txt")).unwrap(), "hello\n");
+            assert_eq!(fs::read_to_string(extracted.join("sub/odd")).unwrap(), "three");
+            assert!(extracted.join("sub/empty").is_dir());
+            #[cfg(unix)]
+            assert_eq!(fs::read_link(extracted.join("link")).unwrap(), Path::new("a.txt"));
+        }
+    }
+
+    #[test]
+    fn rejects_crc_mismatch() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = root.path().join("tree");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("a.txt"), "hello\n").unwrap();
+        let archive = cpio_folder(&folder, None, Some("crc"), Some(root.path().to_str().unwrap())).unwrap();
+
+        let mut data = fs::read(&archive).unwrap();
+        let position = data.windows(6).position(|window| window == b"hello\n").unwrap();
+        data[position] = b'j';
+        let result = unpack_cpio_stream(&data[..], &root.path().join("out"));
+        assert!(result.unwrap_err().to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn rejects_oversized_names() {
+        let root = tempfile::tempdir().unwrap();
+        let mut data = fs::read(Path::new(FIXTURES).join("links.newc")).unwrap();
+        // c_namesize is the twelfth eight digit field after the magic
+        data[94..102].copy_from_slice(b"FFFFFFFF");
+        let result = unpack_cpio_stream(&data[..], &root.path().join("out"));
+        assert!(result.unwrap_err().to_string().contains("too long"));
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use tar::Archive;
+use anyhow::{self, Result, Context};
+use crate::ar::ar_utils::for_each_member;
+use crate::tar::tar_utils::{decompressing_reader, unpack_tar_stream};
+
+/// Control metadata of a Debian package and where its data was extracted.
+pub struct DebPackage {
+    /// Contents of the `control` file.
+    pub control: String,
+    /// Other files of the control archive, such as maintainer scripts.
+    pub control_files: Vec<String>,
+    /// Directory the data payload was extracted into.
+    pub output_dir: PathBuf,
+}
+
+pub fn undeb_file(
+    deb_file_path: &Path,
+    file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<DebPackage> {
+    // Ensure the input package exists
+    if !deb_file_path.is_file() {
+        anyhow::bail!("The provided deb file path does not exist or is not a file.");
+    }
+
+    // Get the package's name to use as the base name if no name is provided
+    let deb_file_name = deb_file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine deb file name"))?;
+    let base_name = file_name.unwrap_or_else(|| {
+        deb_file_name.strip_suffix(".deb").unwrap_or(deb_file_name)
+    });
+
+    // Determine the base output directory
+    let base_output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => deb_file_path.parent().unwrap_or_else(|| Path::new(".")).join(format!("{}_extracted", base_name)),
+    };
+
+    // Resolve conflicts by appending a number to the output directory
+    let mut output_path = base_output_path.clone();
+    let mut counter = 1;
+    while output_path.exists() {
+        output_path = base_output_path.with_file_name(format!(
+            "{}-{}",
+            base_output_path.file_name().unwrap().to_str().unwrap(),
+            counter
+        ));
+        counter += 1;
+    }
+
+    // Create the output directory
+    fs::create_dir_all(&output_path)
+        .with_context(|| format!("Failed to create output directory {:?}", output_path))?;
+
+    // Walk the outer ar archive: debian-binary, control.tar.* and data.tar.*
+    let deb_file = File::open(deb_file_path)
+        .with_context(|| format!("Failed to open deb file {:?}", deb_file_path))?;
+    let mut format_version = None;
+    let mut control = None;
+    let mut control_files = Vec::new();
+    let mut has_data = false;
+    for_each_member(BufReader::new(deb_file), |member, data| {
+        if member.name == "debian-binary" {
+            let mut version = String::new();
+            data.read_to_string(&mut version)?;
+            format_version = Some(version.trim().to_string());
+        } else if member.name.starts_with("control.tar") {
+            let reader = decompressing_reader(BufReader::new(data))
+                .with_context(|| format!("Failed to read {}", member.name))?;
+            let mut archive = Archive::new(reader);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let path = entry.path()?.to_string_lossy().into_owned();
+                let name = path.trim_start_matches("./").trim_end_matches('/');
+                if name == "control" {
+                    let mut text = String::new();
+                    entry.read_to_string(&mut text)?;
+                    control = Some(text);
+                } else if !name.is_empty() {
+                    control_files.push(name.to_string());
+                }
+            }
+        } else if member.name.starts_with("data.tar") {
+            let reader = decompressing_reader(BufReader::new(data))
+                .with_context(|| format!("Failed to read {}", member.name))?;
+            unpack_tar_stream(reader, &output_path)?;
+            has_data = true;
+        }
+        Ok(true)
+    })
+    .with_context(|| format!("Failed to read deb file {:?}", deb_file_path))?;
+
+    // Make sure this really was a Debian package
+    match format_version.as_deref() {
+        Some(version) if version.starts_with("2.") => {}
+        Some(version) => anyhow::bail!("Unsupported deb format version: {}", version),
+        None => anyhow::bail!("{:?} is not a Debian package (missing debian-binary)", deb_file_path),
+    }
+    let control = control.ok_or_else(|| anyhow::anyhow!("The package has no control file"))?;
+    if !has_data {
+        anyhow::bail!("The package has no data archive");
+    }
+
+    Ok(DebPackage {
+        control,
+        control_files,
+        output_dir: output_path,
+    })
+}
@@ -1,17 +1,26 @@
 // Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
 
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek};
 use std::path::{Path, PathBuf};
-use flate2::Compression;
-use flate2::read::GzDecoder;
-use flate2::write::GzEncoder;
+use std::time::{Duration, UNIX_EPOCH};
+use flate2::{Compression, GzBuilder};
+use flate2::bufread::GzDecoder;
 use anyhow::{self, Result};
+use crate::archive::archive_utils::set_modified;
 
+// Magic bytes that open every gzip member
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Compresses a file with gzip. The header records the original file name
+/// and modification time, which `no_name` leaves out for reproducible
+/// output, along with an optional comment.
 pub fn gzip_file(
     input_path: &Path,
     output_file_name: Option<&str>,
     compression_level: Option<u32>,
+    comment: Option<&str>,
+    no_name: bool,
     output_dir: Option<&str>,
 ) -> Result<PathBuf> {
     if !input_path.is_file() {
@@ -25,7 +34,7 @@ pub fn gzip_file(
         .into_owned();
 
     let gz_name = output_file_name
-        .map(|n| PathBuf::from(n))
+        .map(PathBuf::from)
         .unwrap_or_else(|| PathBuf::from(format!("{}.gz", file_name)));
 
     let gz_path = output_dir
@@ -34,21 +43,62 @@ pub fn gzip_file(
         .join(gz_name);
 
     let input_file = File::open(input_path)?;
+    let mut header = GzBuilder::new();
+    if !no_name {
+        // gzip stores the time as 32-bit seconds, with zero meaning unknown
+        let mtime = input_file
+            .metadata()?
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| u32::try_from(duration.as_secs()).unwrap_or(0));
+        header = header.filename(file_name.as_bytes()).mtime(mtime);
+    }
+    if let Some(comment) = comment {
+        if comment.contains('\0') {
+            anyhow::bail!("A gzip comment cannot contain NUL characters");
+        }
+        header = header.comment(comment.as_bytes());
+    }
     let output_file = File::create(&gz_path)?;
 
     let compression = Compression::new(compression_level.unwrap_or(6));
-    let mut encoder = GzEncoder::new(BufWriter::new(output_file), compression);
+    let mut encoder = header.write(BufWriter::new(output_file), compression);
     std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
     encoder.finish()?;
 
     Ok(gz_path)
 }
 
+/// One member of a gzip file, which may hold several back to back.
+pub struct GzipMember {
+    /// Offset of the member's header in the compressed file.
+    pub offset: u64,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+}
+
+/// A decompressed gzip file and the members it was made of.
+pub struct GunzippedFile {
+    pub path: PathBuf,
+    pub members: Vec<GzipMember>,
+    /// Comment stored in the first member's header, if any.
+    pub comment: Option<String>,
+    /// Offset of data after the last member that was not gzip and was
+    /// ignored, as gzip does with a warning.
+    pub trailing_garbage: Option<u64>,
+}
+
+/// Decompresses a gzip file. Like `gzip -N`, the output takes the name and
+/// modification time stored in the header unless `no_name` is set; an
+/// explicit output name still wins over the stored one. Existing files are
+/// never overwritten; a numbered name is picked instead.
 pub fn gunzip_file(
     gz_path: &Path,
     output_file_name: Option<&str>,
+    no_name: bool,
     output_dir: Option<&str>,
-) -> Result<PathBuf> {
+) -> Result<GunzippedFile> {
     if !gz_path.is_file() {
         anyhow::bail!("Provided path is not a file: {:?}", gz_path);
     }
@@ -59,19 +109,172 @@ pub fn gunzip_file(
         .to_string_lossy()
         .into_owned();
 
+    // Read the first header up front, as it decides the output name
+    let input_file = File::open(gz_path)?;
+    let mut reader = CountingReader {
+        inner: BufReader::new(input_file),
+        position: 0,
+    };
+    let header = match reader.fill_buf()?.starts_with(GZIP_MAGIC) {
+        true => GzDecoder::new(&mut reader).header().cloned(),
+        false => None,
+    };
+    reader.inner.rewind()?;
+    reader.position = 0;
+
+    // Only the final component of a stored name is used, so a crafted
+    // header cannot place the output elsewhere
+    let stored_name = header
+        .as_ref()
+        .filter(|_| !no_name)
+        .and_then(|header| header.filename())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .and_then(|name| {
+            let name = name.replace('\\', "/");
+            Path::new(&name).file_name().map(|name| name.to_string_lossy().into_owned())
+        });
+
     let out_name = output_file_name
         .map(String::from)
+        .or(stored_name)
         .unwrap_or(default_name);
 
-    let out_path = output_dir
+    let out_dir = output_dir
         .map(Path::new)
-        .unwrap_or_else(|| gz_path.parent().unwrap_or_else(|| Path::new(".")))
-        .join(out_name);
+        .unwrap_or_else(|| gz_path.parent().unwrap_or_else(|| Path::new(".")));
+    let mut out_path = out_dir.join(&out_name);
+    if same_file(&out_path, gz_path) {
+        anyhow::bail!("Output {:?} would overwrite the compressed input", out_path);
+    }
 
-    let input_file = File::open(gz_path)?;
-    let mut decoder = GzDecoder::new(BufReader::new(input_file));
-    let mut output_file = File::create(&out_path)?;
-    std::io::copy(&mut decoder, &mut output_file)?;
+    // The stored name may match the input or a neighbour, so number the
+    // output instead of truncating whatever is there
+    let (stem, extension) = match out_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (out_name.as_str(), None),
+    };
+    let mut counter = 1;
+    while out_path.symlink_metadata().is_ok() {
+        out_path = out_dir.join(match extension {
+            Some(extension) => format!("{}-{}.{}", stem, counter, extension),
+            None => format!("{}-{}", stem, counter),
+        });
+        counter += 1;
+    }
+
+    if !reader.fill_buf()?.starts_with(GZIP_MAGIC) {
+        anyhow::bail!("Not a gzip file: {:?}", gz_path);
+    }
+
+    // A failed decode should not leave a partial file behind
+    let output_file = File::create_new(&out_path)?;
+    let (members, trailing_garbage) = match decode_members(&mut reader, output_file) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            let _ = std::fs::remove_file(&out_path);
+            return Err(err);
+        }
+    };
+
+    // A zero time means none was stored
+    let mtime = header.as_ref().map_or(0, |header| header.mtime());
+    if !no_name && mtime != 0 {
+        set_modified(&out_path, Some(UNIX_EPOCH + Duration::from_secs(mtime as u64)))?;
+    }
+
+    Ok(GunzippedFile {
+        path: out_path,
+        members,
+        comment: header
+            .as_ref()
+            .and_then(|header| header.comment())
+            .map(|comment| String::from_utf8_lossy(comment).into_owned()),
+        trailing_garbage,
+    })
+}
+
+// Concatenated gzip files decompress to the concatenation of their members,
+// so keep decoding until the input runs out or stops looking like gzip
+fn decode_members<R: BufRead>(
+    reader: &mut CountingReader<R>,
+    output_file: File,
+) -> Result<(Vec<GzipMember>, Option<u64>)> {
+    let mut output_file = BufWriter::new(output_file);
+    let mut members = Vec::new();
+    let mut trailing_garbage = None;
+    loop {
+        let next = reader.fill_buf()?;
+        if next.is_empty() {
+            break;
+        }
+        if !next.starts_with(GZIP_MAGIC) {
+            // Padding with zeros is tolerated silently, as gzip does
+            let zeros_only = next.iter().all(|&byte| byte == 0);
+            if !(zeros_only && skip_zeros(reader)?) {
+                trailing_garbage = Some(reader.position);
+            }
+            break;
+        }
+
+        let offset = reader.position;
+        let mut decoder = GzDecoder::new(&mut *reader);
+        let uncompressed_size = std::io::copy(&mut decoder, &mut output_file)
+            .map_err(|err| anyhow::anyhow!("Failed to decompress gzip member at offset {}: {}", offset, err))?;
+        members.push(GzipMember {
+            offset,
+            compressed_size: reader.position - offset,
+            uncompressed_size,
+        });
+    }
+    output_file.into_inner().map_err(|err| err.into_error())?;
+    Ok((members, trailing_garbage))
+}
+
+// Whether two paths name the same existing file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Consumes a run of zero bytes, reporting whether it lasted to the end
+fn skip_zeros<R: BufRead>(reader: &mut R) -> Result<bool> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(true);
+        }
+        let zeros = buffer.iter().take_while(|&&byte| byte == 0).count();
+        let all_zeros = zeros == buffer.len();
+        reader.consume(zeros);
+        if !all_zeros {
+            return Ok(false);
+        }
+    }
+}
+
+// Tracks how far into the compressed file decoding has got
+struct CountingReader<R: BufRead> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: BufRead> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
 
-    Ok(out_path)
-}
\ No newline at end of file
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.position += amount as u64;
+    }
+}
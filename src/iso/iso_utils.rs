@@ -0,0 +1,596 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{self, Result, Context};
+use crate::archive::archive_utils::{extraction_dir, output_path, set_mode, set_modified};
+
+// Volume descriptors start at sector 16 and are always 2048 bytes apart
+const DESCRIPTOR_START: u64 = 16 * 2048;
+const DESCRIPTOR_SIZE: usize = 2048;
+const STANDARD_ID: &[u8] = b"CD001";
+
+// Volume descriptor types
+const PRIMARY_DESCRIPTOR: u8 = 1;
+const SUPPLEMENTARY_DESCRIPTOR: u8 = 2;
+const TERMINATOR_DESCRIPTOR: u8 = 255;
+
+// Directory record flags
+const FLAG_DIRECTORY: u8 = 0x02;
+const FLAG_MULTI_EXTENT: u8 = 0x80;
+
+// File type bits of a Unix st_mode
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFDIR: u32 = 0o040000;
+
+// Guards against directory loops in malformed images
+const MAX_DEPTH: usize = 64;
+
+/// A file, directory or symlink stored in an ISO 9660 image.
+pub struct IsoEntry {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub mode: Option<u32>,
+    pub modified: Option<SystemTime>,
+    pub symlink: Option<String>,
+    extents: Vec<(u64, u64)>,
+}
+
+// Which naming scheme the directory tree is read with
+#[derive(Clone, Copy, PartialEq)]
+enum Names {
+    Plain,
+    Joliet,
+    RockRidge { skip: usize },
+}
+
+// The parts of a Rock Ridge system use area fecr cares about
+#[derive(Default)]
+struct RockRidge {
+    name: Option<String>,
+    mode: Option<u32>,
+    modified: Option<SystemTime>,
+    symlink: Option<String>,
+    child_link: Option<u64>,
+    relocated: bool,
+}
+
+// An open image together with the volume it is read through
+struct IsoImage {
+    file: File,
+    block_size: u64,
+    names: Names,
+    root: (u64, u64),
+}
+
+pub fn list_iso_file(iso_file_path: &Path) -> Result<Vec<IsoEntry>> {
+    // Ensure the file exists and is a valid file
+    if !iso_file_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", iso_file_path);
+    }
+
+    let mut image = IsoImage::open(iso_file_path)?;
+    image.entries()
+}
+
+pub fn uniso_file(iso_file_path: &Path, file_name: Option<&str>, output_dir: Option<&str>) -> Result<PathBuf> {
+    // Ensure the file exists and is a valid file
+    if !iso_file_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", iso_file_path);
+    }
+
+    // Determine the base output directory name
+    let base_name = match file_name {
+        Some(name) => name.to_string(),
+        None => iso_file_path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+
+    // Determine the output directory, numbering it when the name is taken
+    let base_dir = match output_dir {
+        Some(dir) => Path::new(dir),
+        None => iso_file_path.parent().unwrap_or_else(|| Path::new(".")),
+    };
+    let output_dir = extraction_dir(base_dir, &base_name);
+
+    // Read the whole directory tree before creating anything
+    let mut image = IsoImage::open(iso_file_path)?;
+    let entries = image.entries()?;
+
+    // Create the output directory
+    fs::create_dir_all(&output_dir)?;
+
+    let mut directories = Vec::new();
+    for entry in &entries {
+        let out_path = output_path(&output_dir, &entry.path)?;
+
+        if entry.is_dir {
+            // Create directories
+            fs::create_dir_all(&out_path)
+                .with_context(|| format!("Failed to create directory {:?}", out_path))?;
+            directories.push((out_path, entry.modified, entry.mode));
+            continue;
+        }
+
+        // Create parent directories if needed
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        #[cfg(unix)]
+        if let Some(target) = &entry.symlink {
+            std::os::unix::fs::symlink(target, &out_path)
+                .with_context(|| format!("Failed to create symlink {:?}", out_path))?;
+            continue;
+        }
+
+        // Write the file from each of its extents
+        let mut outfile = BufWriter::new(
+            File::create(&out_path).with_context(|| format!("Failed to create file {:?}", out_path))?,
+        );
+        for (offset, length) in &entry.extents {
+            image.file.seek(SeekFrom::Start(*offset))?;
+            let copied = io::copy(&mut (&mut image.file).take(*length), &mut outfile)?;
+            if copied != *length {
+                anyhow::bail!("Unexpected end of image while reading {:?}", entry.path);
+            }
+        }
+        outfile.into_inner().map_err(|err| err.into_error())?;
+        set_modified(&out_path, entry.modified)?;
+        set_mode(&out_path, entry.mode)?;
+    }
+
+    // Restore directory metadata last, as extracting their contents bumps the
+    // timestamps and a read-only mode would block writing them
+    for (path, modified, mode) in directories.iter().rev() {
+        set_modified(path, *modified)?;
+        set_mode(path, *mode)?;
+    }
+
+    Ok(output_dir)
+}
+
+impl IsoImage {
+    // Reads the volume descriptors, preferring Rock Ridge, then Joliet names
+    fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path).with_context(|| format!("Failed to open ISO image {:?}", path))?;
+        file.seek(SeekFrom::Start(DESCRIPTOR_START))?;
+
+        let mut primary = None;
+        let mut joliet = None;
+        loop {
+            let mut descriptor = vec![0; DESCRIPTOR_SIZE];
+            file.read_exact(&mut descriptor)
+                .with_context(|| format!("{:?} is not an ISO 9660 image", path))?;
+            if &descriptor[1..6] != STANDARD_ID {
+                anyhow::bail!("{:?} is not an ISO 9660 image", path);
+            }
+            match descriptor[0] {
+                PRIMARY_DESCRIPTOR if primary.is_none() => primary = Some(descriptor),
+                SUPPLEMENTARY_DESCRIPTOR if is_joliet(&descriptor) => joliet = Some(descriptor),
+                TERMINATOR_DESCRIPTOR => break,
+                _ => {}
+            }
+        }
+        let primary = primary.ok_or_else(|| anyhow::anyhow!("{:?} has no primary volume descriptor", path))?;
+
+        let mut image = IsoImage {
+            file,
+            block_size: u16::from_le_bytes([primary[128], primary[129]]) as u64,
+            names: Names::Plain,
+            root: root_extent(&primary),
+        };
+        if image.block_size == 0 {
+            anyhow::bail!("{:?} has an invalid logical block size", path);
+        }
+
+        // Rock Ridge announces itself with an SP entry in the root's "." record
+        let root_dir = image.read_extent(image.root.0, image.root.1)?;
+        let dot = record(&root_dir, 0).ok_or_else(|| anyhow::anyhow!("The root directory is empty"))?;
+        let system_use = system_use_area(dot);
+        if system_use.len() >= 7 && &system_use[..2] == b"SP" && system_use[4..6] == [0xbe, 0xef] {
+            image.names = Names::RockRidge { skip: system_use[6] as usize };
+        } else if let Some(joliet) = joliet {
+            image.names = Names::Joliet;
+            image.root = root_extent(&joliet);
+        }
+        Ok(image)
+    }
+
+    // Reads `length` bytes starting at logical block `block`
+    fn read_extent(&mut self, block: u64, length: u64) -> Result<Vec<u8>> {
+        self.file.seek(SeekFrom::Start(block * self.block_size))?;
+        let mut data = Vec::new();
+        (&mut self.file).take(length).read_to_end(&mut data)?;
+        if data.len() as u64 != length {
+            anyhow::bail!("Unexpected end of image");
+        }
+        Ok(data)
+    }
+
+    // Lists every entry of the image, parents before their contents
+    fn entries(&mut self) -> Result<Vec<IsoEntry>> {
+        let mut entries = Vec::new();
+        let mut visited = HashSet::new();
+        let root = self.root;
+        self.walk(root, "", 0, &mut visited, &mut entries)?;
+        Ok(entries)
+    }
+
+    fn walk(
+        &mut self,
+        (block, length): (u64, u64),
+        prefix: &str,
+        depth: usize,
+        visited: &mut HashSet<u64>,
+        entries: &mut Vec<IsoEntry>,
+    ) -> Result<bool> {
+        if depth > MAX_DEPTH || !visited.insert(block) {
+            anyhow::bail!("The directory tree is too deep or contains a loop");
+        }
+
+        let data = self.read_extent(block, length)?;
+        let mut position = 0;
+        let mut pending: Option<IsoEntry> = None;
+        let mut has_relocated = false;
+        while position < data.len() {
+            // Records never cross a block boundary; a zero length pads to the next one
+            let Some(dir_record) = record(&data, position) else {
+                let block_size = self.block_size as usize;
+                position = (position / block_size + 1) * block_size;
+                continue;
+            };
+            position += dir_record.len();
+
+            // Skip the "." and ".." records
+            let raw_name = &dir_record[33..33 + dir_record[32] as usize];
+            if raw_name == [0] || raw_name == [1] {
+                continue;
+            }
+
+            let flags = dir_record[25];
+            let extent = u32::from_le_bytes(dir_record[2..6].try_into()?) as u64;
+            let size = u32::from_le_bytes(dir_record[10..14].try_into()?) as u64;
+            let rock_ridge = match self.names {
+                Names::RockRidge { skip } => self.rock_ridge(system_use_area(dir_record).get(skip..).unwrap_or(&[]))?,
+                _ => RockRidge::default(),
+            };
+            if rock_ridge.relocated {
+                has_relocated = true;
+                continue;
+            }
+
+            // Later extents of a multi-extent file continue the pending entry
+            if let Some(entry) = pending.as_mut() {
+                entry.extents.push((extent * self.block_size, size));
+                entry.size += size;
+                if flags & FLAG_MULTI_EXTENT == 0 {
+                    entries.push(pending.take().unwrap());
+                }
+                continue;
+            }
+
+            let name = match (&rock_ridge.name, self.names) {
+                (Some(name), _) => name.clone(),
+                (None, Names::Joliet) => joliet_name(raw_name),
+                _ => plain_name(raw_name),
+            };
+            if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+                anyhow::bail!("Invalid file name {:?} in the image", name);
+            }
+            let path = format!("{}{}", prefix, name);
+            let is_dir = flags & FLAG_DIRECTORY != 0
+                || rock_ridge.child_link.is_some()
+                || rock_ridge.mode.is_some_and(|mode| mode & S_IFMT == S_IFDIR);
+            let symlink = rock_ridge
+                .symlink
+                .filter(|_| rock_ridge.mode.is_none_or(|mode| mode & S_IFMT == S_IFLNK));
+
+            let entry = IsoEntry {
+                path: path.clone(),
+                is_dir,
+                size: if is_dir { 0 } else { size },
+                mode: rock_ridge.mode,
+                modified: rock_ridge.modified.or_else(|| record_time(&dir_record[18..25])),
+                symlink,
+                extents: vec![(extent * self.block_size, size)],
+            };
+
+            if is_dir {
+                // Relocated directories are reached through their child link
+                let location = match rock_ridge.child_link {
+                    Some(child) => {
+                        let child_dir = self.read_extent(child, self.block_size)?;
+                        let dot = record(&child_dir, 0)
+                            .ok_or_else(|| anyhow::anyhow!("Broken relocated directory {:?}", path))?;
+                        (child, u32::from_le_bytes(dot[10..14].try_into()?) as u64)
+                    }
+                    None => (extent, size),
+                };
+                // Hide holders such as rr_moved that only contain relocated directories
+                let index = entries.len();
+                entries.push(entry);
+                let moved = self.walk(location, &format!("{}/", path), depth + 1, visited, entries)?;
+                if moved && entries.len() == index + 1 {
+                    entries.pop();
+                }
+            } else if flags & FLAG_MULTI_EXTENT != 0 {
+                pending = Some(entry);
+            } else {
+                entries.push(entry);
+            }
+        }
+
+        Ok(has_relocated)
+    }
+
+    // Parses the SUSP entries of a system use area, following continuation areas
+    fn rock_ridge(&mut self, area: &[u8]) -> Result<RockRidge> {
+        let mut info = RockRidge::default();
+        let mut area = area.to_vec();
+        let mut continuations = 0;
+        let mut name = String::new();
+        let mut target = String::new();
+        let mut target_continues = false;
+
+        loop {
+            let mut continuation = None;
+            let mut position = 0;
+            while position + 4 <= area.len() {
+                let length = area[position + 2] as usize;
+                if length < 4 || position + length > area.len() {
+                    break;
+                }
+                let entry = &area[position..position + length];
+                let data = &entry[4..];
+                match &entry[..2] {
+                    b"NM" if !data.is_empty() => {
+                        name.push_str(&String::from_utf8_lossy(&data[1..]));
+                        if data[0] & 0x01 == 0 {
+                            info.name = Some(std::mem::take(&mut name));
+                        }
+                    }
+                    b"PX" if data.len() >= 8 => {
+                        info.mode = Some(u32::from_le_bytes(data[..4].try_into()?));
+                    }
+                    b"SL" if !data.is_empty() => {
+                        let mut components = &data[1..];
+                        while components.len() >= 2 {
+                            let (flags, len) = (components[0], components[1] as usize);
+                            let content = components.get(2..2 + len).unwrap_or(&[]);
+                            if !target.is_empty() && !target_continues && !target.ends_with('/') {
+                                target.push('/');
+                            }
+                            match flags & 0x0e {
+                                0x02 => target.push('.'),
+                                0x04 => target.push_str(".."),
+                                0x08 => target.push('/'),
+                                _ => target.push_str(&String::from_utf8_lossy(content)),
+                            }
+                            target_continues = flags & 0x01 != 0;
+                            components = components.get(2 + len..).unwrap_or(&[]);
+                        }
+                        if data[0] & 0x01 == 0 {
+                            info.symlink = Some(std::mem::take(&mut target));
+                        }
+                    }
+                    b"TF" if !data.is_empty() => {
+                        // Timestamps are stored in flag order: creation, then modification
+                        let long_form = data[0] & 0x80 != 0;
+                        let width = if long_form { 17 } else { 7 };
+                        let skip = usize::from(data[0] & 0x01 != 0);
+                        if data[0] & 0x02 != 0 {
+                            let start = 1 + skip * width;
+                            if let Some(stamp) = data.get(start..start + width) {
+                                info.modified = if long_form { descriptor_time(stamp) } else { record_time(stamp) };
+                            }
+                        }
+                    }
+                    b"CL" if data.len() >= 4 => {
+                        info.child_link = Some(u32::from_le_bytes(data[..4].try_into()?) as u64);
+                    }
+                    b"RE" => info.relocated = true,
+                    b"CE" if data.len() >= 20 => {
+                        let block = u32::from_le_bytes(data[..4].try_into()?) as u64;
+                        let offset = u32::from_le_bytes(data[8..12].try_into()?) as u64;
+                        let length = u32::from_le_bytes(data[16..20].try_into()?) as u64;
+                        continuation = Some((block, offset, length));
+                    }
+                    b"ST" => break,
+                    _ => {}
+                }
+                position += length;
+            }
+
+            // Follow the continuation area, if any, a bounded number of times
+            match continuation {
+                Some((block, offset, length)) if continuations < 16 => {
+                    continuations += 1;
+                    // A continuation area lies within one logical block
+                    if offset + length > self.block_size {
+                        anyhow::bail!("Invalid Rock Ridge continuation area at block {}", block);
+                    }
+                    area = self.read_extent(block, offset + length)?.split_off(offset as usize);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+// Returns the directory record starting at `position`, if there is one
+fn record(data: &[u8], position: usize) -> Option<&[u8]> {
+    let length = *data.get(position)? as usize;
+    if length < 34 {
+        return None;
+    }
+    let dir_record = data.get(position..position + length)?;
+    if 33 + dir_record[32] as usize > length {
+        return None;
+    }
+    Some(dir_record)
+}
+
+// The system use area follows the name and its padding byte
+fn system_use_area(dir_record: &[u8]) -> &[u8] {
+    let name_length = dir_record[32] as usize;
+    let start = 33 + name_length + (name_length + 1) % 2;
+    dir_record.get(start..).unwrap_or(&[])
+}
+
+// Extent and size of the root directory record of a volume descriptor
+fn root_extent(descriptor: &[u8]) -> (u64, u64) {
+    let root = &descriptor[156..190];
+    (
+        u32::from_le_bytes([root[2], root[3], root[4], root[5]]) as u64,
+        u32::from_le_bytes([root[10], root[11], root[12], root[13]]) as u64,
+    )
+}
+
+// Joliet volumes are supplementary descriptors with a UCS-2 escape sequence
+fn is_joliet(descriptor: &[u8]) -> bool {
+    matches!(&descriptor[88..91], b"%/@" | b"%/C" | b"%/E")
+}
+
+// Strips the ";1" version and the trailing dot of an ISO 9660 name
+fn plain_name(raw_name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(raw_name);
+    let name = name.split(';').next().unwrap_or_default();
+    name.strip_suffix('.').unwrap_or(name).to_string()
+}
+
+// Decodes a big endian UCS-2 Joliet name
+fn joliet_name(raw_name: &[u8]) -> String {
+    let units: Vec<u16> = raw_name
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    let name = String::from_utf16_lossy(&units);
+    name.split(';').next().unwrap_or_default().to_string()
+}
+
+// Converts a 7 byte directory record timestamp
+fn record_time(stamp: &[u8]) -> Option<SystemTime> {
+    let [year, month, day, hour, minute, second, offset] = stamp.try_into().ok()?;
+    unix_time(1900 + year as i64, month, day, hour, minute, second, offset as i8)
+}
+
+// Converts a 17 byte volume descriptor style timestamp
+fn descriptor_time(stamp: &[u8]) -> Option<SystemTime> {
+    let digits = std::str::from_utf8(&stamp[..14]).ok()?;
+    let field = |start: usize, len: usize| digits.get(start..start + len)?.parse::<u8>().ok();
+    let year = digits.get(..4)?.parse::<i64>().ok()?;
+    unix_time(year, field(4, 2)?, field(6, 2)?, field(8, 2)?, field(10, 2)?, field(12, 2)?, stamp[16] as i8)
+}
+
+// Turns a civil date with a GMT offset in 15 minute steps into a SystemTime
+fn unix_time(year: i64, month: u8, day: u8, hour: u8, minute: u8, second: u8, offset: i8) -> Option<SystemTime> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since the epoch, following Howard Hinnant's days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64 - offset as i64 * 900;
+    Some(if seconds >= 0 {
+        UNIX_EPOCH + Duration::from_secs(seconds as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/iso");
+
+    // Decompresses a fixture image into a scratch directory
+    fn image(fixture: &str) -> (tempfile::TempDir, PathBuf) {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join(fixture.trim_end_matches(".gz"));
+        let mut decoder = GzDecoder::new(File::open(Path::new(FIXTURES).join(fixture)).unwrap());
+        io::copy(&mut decoder, &mut File::create(&path).unwrap()).unwrap();
+        (root, path)
+    }
+
+    fn long_name() -> String {
+        format!("long_{}.txt", "x".repeat(200))
+    }
+
+    #[test]
+    fn lists_rock_ridge_names() {
+        let (_root, path) = image("rr.iso.gz");
+        let entries = list_iso_file(&path).unwrap();
+        let paths: Vec<_> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        assert!(paths.contains(&"a/b/c/d/e/f/g/h/i/deep.txt"));
+        assert!(paths.contains(&long_name().as_str()));
+        assert!(!paths.iter().any(|path| path.starts_with("rr_moved")));
+        let link = entries.iter().find(|entry| entry.path == "link").unwrap();
+        assert_eq!(link.symlink.as_deref(), Some("hello.txt"));
+    }
+
+    #[test]
+    fn extracts_rock_ridge_image() {
+        let (root, path) = image("rr.iso.gz");
+        let output = uniso_file(&path, Some("out"), Some(root.path().to_str().unwrap())).unwrap();
+        assert_eq!(fs::read_to_string(output.join("hello.txt")).unwrap(), "hello\n");
+        let numbers: String = (1..=300).map(|n| format!("{}\n", n)).collect();
+        assert_eq!(fs::read_to_string(output.join("numbers.txt")).unwrap(), numbers);
+        assert_eq!(fs::read_to_string(output.join("a/b/c/d/e/f/g/h/i/deep.txt")).unwrap(), "deep file\n");
+        assert_eq!(fs::read_to_string(output.join(long_name())).unwrap(), "long name\n");
+        #[cfg(unix)]
+        assert_eq!(fs::read_link(output.join("link")).unwrap(), Path::new("hello.txt"));
+    }
+
+    #[test]
+    fn numbers_taken_output_inside_output_dir() {
+        let (root, path) = image("rr.iso.gz");
+        let output = root.path().join("output");
+        fs::create_dir_all(output.join("rr")).unwrap();
+        let extracted = uniso_file(&path, None, Some(output.to_str().unwrap())).unwrap();
+        assert_eq!(extracted, output.join("rr-1"));
+        assert_eq!(fs::read_to_string(extracted.join("hello.txt")).unwrap(), "hello\n");
+    }
+
+    #[test]
+    fn extracts_joliet_image() {
+        let (root, path) = image("joliet.iso.gz");
+        let output = uniso_file(&path, Some("out"), Some(root.path().to_str().unwrap())).unwrap();
+        assert_eq!(fs::read_to_string(output.join("hello.txt")).unwrap(), "hello\n");
+        assert_eq!(fs::read_to_string(output.join("docs/Mixed Case Name.txt")).unwrap(), "mixed Case\n");
+    }
+
+    #[test]
+    fn rejects_oversized_continuation_area() {
+        let (_root, path) = image("rr.iso.gz");
+        let mut data = fs::read(&path).unwrap();
+        let positions: Vec<_> = data
+            .windows(4)
+            .enumerate()
+            .filter(|(_, window)| *window == b"CE\x1c\x01")
+            .map(|(position, _)| position)
+            .collect();
+        for position in positions {
+            data[position + 20..position + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        fs::write(&path, data).unwrap();
+        let err = list_iso_file(&path).err().unwrap();
+        assert!(err.to_string().contains("continuation area"));
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use lz4::{Decoder, EncoderBuilder};
+use anyhow::{self, Result};
+
+pub fn lz4_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let level = check_level(compression_level)?;
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let lz4_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.lz4", file_name)));
+
+    let lz4_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(lz4_name);
+
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(&lz4_path)?;
+
+    let mut encoder = EncoderBuilder::new()
+        .level(level)
+        .build(BufWriter::new(output_file))?;
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    let (_, result) = encoder.finish();
+    result?;
+
+    Ok(lz4_path)
+}
+
+pub fn unlz4_file(
+    lz4_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !lz4_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", lz4_path);
+    }
+
+    let default_name = lz4_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .lz4 file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| lz4_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(lz4_path)?;
+    let mut decoder = Decoder::new(BufReader::new(input_file))?;
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
+
+/// Validates an lz4 level. Levels 0-2 use the fast compressor, while
+/// 3-12 switch to the slower high-compression (HC) mode.
+fn check_level(compression_level: Option<u32>) -> Result<u32> {
+    let level = compression_level.unwrap_or(0);
+    if level > 12 {
+        anyhow::bail!("Invalid lz4 compression level: {} (expected 0 to 12)", level);
+    }
+
+    Ok(level)
+}
\ No newline at end of file
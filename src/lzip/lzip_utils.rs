@@ -0,0 +1,334 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use crc32fast::Hasher;
+use xz2::stream::{Action, LzmaOptions, Status, Stream};
+use xz2::write::XzEncoder;
+use anyhow::{self, Result};
+
+// Every lzip member starts with this magic string followed by the version
+pub const LZIP_MAGIC: &[u8] = b"LZIP";
+const LZIP_VERSION: u8 = 1;
+
+// Sizes of the member header and trailer surrounding the LZMA data
+const HEADER_LEN: u64 = 6;
+const TRAILER_LEN: u64 = 20;
+
+// Length of the `.lzma` (lzma_alone) header liblzma reads and writes
+const ALONE_HEADER_LEN: usize = 13;
+
+// lzip fixes the LZMA literal context, literal position and position bits
+// to 3, 0 and 2, which the lzma_alone header encodes as this properties byte
+const LZMA_PROPERTIES: u8 = 0x5d;
+
+// Dictionary sizes used for levels 0-9, matching the liblzma presets
+const DICT_SIZES: [u32; 10] = [
+    1 << 18, 1 << 20, 1 << 21, 1 << 22, 1 << 22,
+    1 << 23, 1 << 23, 1 << 24, 1 << 25, 1 << 26,
+];
+
+pub fn lzip_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let level = compression_level.unwrap_or(6);
+    if level > 9 {
+        anyhow::bail!("Invalid lzip compression level: {} (expected 0 to 9)", level);
+    }
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let lz_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.lz", file_name)));
+
+    let lz_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(lz_name);
+
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(&lz_path)?;
+
+    let mut encoder = LzipEncoder::new(BufWriter::new(output_file), level)?;
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.finish()?;
+
+    Ok(lz_path)
+}
+
+pub fn unlzip_file(
+    lz_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !lz_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", lz_path);
+    }
+
+    let default_name = lz_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .lz file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| lz_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(lz_path)?;
+    let mut decoder = LzipDecoder::new(BufReader::new(input_file));
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
+
+/// Writes a single-member lzip stream. liblzma produces the LZMA data in
+/// the `.lzma` container, whose header is dropped in favour of the lzip one.
+pub struct LzipEncoder<W: Write> {
+    encoder: XzEncoder<MemberBody<W>>,
+    crc: Hasher,
+    data_size: u64,
+}
+
+impl<W: Write> LzipEncoder<W> {
+    pub fn new(mut writer: W, level: u32) -> Result<Self> {
+        let dict_size = *DICT_SIZES
+            .get(level as usize)
+            .ok_or_else(|| anyhow::anyhow!("Invalid lzip compression level: {}", level))?;
+
+        let mut options = LzmaOptions::new_preset(level)?;
+        options
+            .dict_size(dict_size)
+            .literal_context_bits(3)
+            .literal_position_bits(0)
+            .position_bits(2);
+        let stream = Stream::new_lzma_encoder(&options)?;
+
+        // Dictionary sizes are powers of two, so the fraction bits stay zero
+        writer.write_all(LZIP_MAGIC)?;
+        writer.write_all(&[LZIP_VERSION, dict_size.trailing_zeros() as u8])?;
+
+        let body = MemberBody { inner: writer, skip: ALONE_HEADER_LEN, written: 0 };
+        Ok(LzipEncoder {
+            encoder: XzEncoder::new_stream(body, stream),
+            crc: Hasher::new(),
+            data_size: 0,
+        })
+    }
+
+    /// Finishes the LZMA stream and writes the member trailer.
+    pub fn finish(self) -> io::Result<W> {
+        let body = self.encoder.finish()?;
+        let member_size = HEADER_LEN + body.written + TRAILER_LEN;
+
+        let mut writer = body.inner;
+        writer.write_all(&self.crc.finalize().to_le_bytes())?;
+        writer.write_all(&self.data_size.to_le_bytes())?;
+        writer.write_all(&member_size.to_le_bytes())?;
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for LzipEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.encoder.write(buf)?;
+        self.crc.update(&buf[..written]);
+        self.data_size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder.flush()
+    }
+}
+
+// Drops the `.lzma` header liblzma emits and counts the LZMA bytes after it
+struct MemberBody<W: Write> {
+    inner: W,
+    skip: usize,
+    written: u64,
+}
+
+impl<W: Write> Write for MemberBody<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.skip > 0 {
+            let skipped = self.skip.min(buf.len());
+            self.skip -= skipped;
+            return Ok(skipped);
+        }
+
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads every member of an lzip stream, verifying the CRC32, data size and
+/// member size stored in each trailer.
+pub struct LzipDecoder<R: BufRead> {
+    reader: R,
+    stream: Option<Stream>,
+    alone_header: [u8; ALONE_HEADER_LEN],
+    alone_header_pos: usize,
+    crc: Hasher,
+    data_size: u64,
+    members: u64,
+}
+
+impl<R: BufRead> LzipDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        LzipDecoder {
+            reader,
+            stream: None,
+            alone_header: [0xff; ALONE_HEADER_LEN],
+            alone_header_pos: 0,
+            crc: Hasher::new(),
+            data_size: 0,
+            members: 0,
+        }
+    }
+
+    // Parses a member header and queues an equivalent `.lzma` header for
+    // liblzma, returning false once the input is exhausted
+    fn start_member(&mut self) -> io::Result<bool> {
+        if self.reader.fill_buf()?.is_empty() {
+            if self.members == 0 {
+                return Err(invalid_data("lzip stream is empty"));
+            }
+            return Ok(false);
+        }
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        self.reader
+            .read_exact(&mut header)
+            .map_err(|_| invalid_data("truncated lzip member header"))?;
+        if &header[..4] != LZIP_MAGIC {
+            return Err(invalid_data("not an lzip member"));
+        }
+        if header[4] != LZIP_VERSION {
+            return Err(invalid_data("unsupported lzip version"));
+        }
+
+        let dict_size = decode_dict_size(header[5])?;
+        self.alone_header = [0xff; ALONE_HEADER_LEN];
+        self.alone_header[0] = LZMA_PROPERTIES;
+        self.alone_header[1..5].copy_from_slice(&dict_size.to_le_bytes());
+        self.alone_header_pos = 0;
+
+        self.stream = Some(Stream::new_lzma_decoder(u64::MAX)?);
+        self.crc = Hasher::new();
+        self.data_size = 0;
+        Ok(true)
+    }
+
+    // Checks the trailer once liblzma has seen the end-of-stream marker
+    fn finish_member(&mut self, lzma_size: u64) -> io::Result<()> {
+        let mut trailer = [0u8; TRAILER_LEN as usize];
+        self.reader.read_exact(&mut trailer)?;
+        let crc = u32::from_le_bytes(trailer[0..4].try_into().unwrap());
+        let data_size = u64::from_le_bytes(trailer[4..12].try_into().unwrap());
+        let member_size = u64::from_le_bytes(trailer[12..20].try_into().unwrap());
+
+        if crc != self.crc.clone().finalize() {
+            return Err(invalid_data("lzip CRC32 mismatch"));
+        }
+        if data_size != self.data_size {
+            return Err(invalid_data("lzip data size mismatch"));
+        }
+        if member_size != HEADER_LEN + lzma_size + TRAILER_LEN {
+            return Err(invalid_data("lzip member size mismatch"));
+        }
+
+        self.members += 1;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for LzipDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if self.stream.is_none() && !self.start_member()? {
+                return Ok(0);
+            }
+            let stream = self.stream.as_mut().unwrap();
+
+            // Feed the synthesized `.lzma` header before the member's data
+            let from_header = self.alone_header_pos < ALONE_HEADER_LEN;
+            let input = if from_header {
+                &self.alone_header[self.alone_header_pos..]
+            } else {
+                self.reader.fill_buf()?
+            };
+            let eof = input.is_empty();
+            let before_in = stream.total_in();
+            let before_out = stream.total_out();
+            let status = stream.process(input, buf, Action::Run)?;
+            let consumed = (stream.total_in() - before_in) as usize;
+            let read = (stream.total_out() - before_out) as usize;
+            if from_header {
+                self.alone_header_pos += consumed;
+            } else {
+                self.reader.consume(consumed);
+            }
+
+            self.crc.update(&buf[..read]);
+            self.data_size += read as u64;
+
+            if status == Status::StreamEnd {
+                let lzma_size = stream.total_in() - ALONE_HEADER_LEN as u64;
+                self.stream = None;
+                self.finish_member(lzma_size)?;
+            }
+            if read > 0 {
+                return Ok(read);
+            }
+            if eof {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated lzip member"));
+            }
+        }
+    }
+}
+
+// Decodes the lzip dictionary size byte: a power of two in the low five bits,
+// minus the number of sixteenths given by the top three bits
+fn decode_dict_size(coded: u8) -> io::Result<u32> {
+    let exponent = u32::from(coded & 0x1f);
+    if !(12..=29).contains(&exponent) {
+        return Err(invalid_data("invalid lzip dictionary size"));
+    }
+
+    let base = 1u32 << exponent;
+    Ok(base - (base / 16) * u32::from(coded >> 5))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
\ No newline at end of file
@@ -0,0 +1,300 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use anyhow::{self, Result};
+
+// Every `compress` stream starts with these bytes followed by a flags byte
+pub const LZW_MAGIC: &[u8] = &[0x1f, 0x9d];
+
+// Flags byte layout: the maximum code width and the block (reset) mode bit
+const MAX_BITS_MASK: u8 = 0x1f;
+const BLOCK_MODE: u8 = 0x80;
+
+// Codes start at nine bits and may grow up to sixteen
+const INIT_BITS: u32 = 9;
+const MAX_BITS: u32 = 16;
+
+// In block mode this code resets the dictionary
+const CLEAR_CODE: u32 = 256;
+
+pub fn uncompress_file(
+    z_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !z_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", z_path);
+    }
+
+    let default_name = z_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .Z file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| z_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(z_path)?;
+    let mut decoder = LzwDecoder::new(BufReader::new(input_file))?;
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
+
+/// Streaming decoder for the LZW format written by Unix `compress`.
+///
+/// Codes are packed LSB first in groups of eight. Whenever the code width
+/// grows or the dictionary is cleared, the rest of the current group is
+/// skipped, mirroring the reference implementation.
+pub struct LzwDecoder<R: BufRead> {
+    reader: R,
+    block_mode: bool,
+    max_bits: u32,
+    n_bits: u32,
+    max_code: u32,
+    free_entry: u32,
+    old_code: Option<u32>,
+    final_char: u8,
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+    bit_buffer: u64,
+    bit_count: u32,
+    group_bits: u64,
+    stack: Vec<u8>,
+    done: bool,
+}
+
+impl<R: BufRead> LzwDecoder<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 3];
+        reader.read_exact(&mut header)?;
+        if header[..2] != *LZW_MAGIC {
+            return Err(invalid_data("not a compress (.Z) stream"));
+        }
+
+        let max_bits = u32::from(header[2] & MAX_BITS_MASK);
+        if !(INIT_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(invalid_data("unsupported compress code width"));
+        }
+        let block_mode = header[2] & BLOCK_MODE != 0;
+
+        let table_size = 1usize << max_bits;
+        Ok(LzwDecoder {
+            reader,
+            block_mode,
+            max_bits,
+            n_bits: INIT_BITS,
+            max_code: (1 << INIT_BITS) - 1,
+            free_entry: if block_mode { CLEAR_CODE + 1 } else { CLEAR_CODE },
+            old_code: None,
+            final_char: 0,
+            prefix: vec![0; table_size],
+            suffix: (0..table_size).map(|code| code as u8).collect(),
+            bit_buffer: 0,
+            bit_count: 0,
+            group_bits: 0,
+            stack: Vec::new(),
+            done: false,
+        })
+    }
+
+    // Reads the next `n_bits` wide code, or None at the end of the input
+    fn read_code(&mut self, n_bits: u32) -> io::Result<Option<u32>> {
+        while self.bit_count < n_bits {
+            let byte = match self.reader.fill_buf()?.first() {
+                Some(&byte) => byte,
+                None => return Ok(None),
+            };
+            self.reader.consume(1);
+            self.bit_buffer |= u64::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let code = (self.bit_buffer & ((1 << n_bits) - 1)) as u32;
+        self.bit_buffer >>= n_bits;
+        self.bit_count -= n_bits;
+        self.group_bits += u64::from(n_bits);
+        Ok(Some(code))
+    }
+
+    // Skips the remainder of the current group of eight codes
+    fn skip_to_group_end(&mut self) -> io::Result<()> {
+        let group_size = u64::from(self.n_bits) * 8;
+        let mut padding = (group_size - self.group_bits % group_size) % group_size;
+        while padding > 0 {
+            let bits = padding.min(u64::from(MAX_BITS)) as u32;
+            if self.read_code(bits)?.is_none() {
+                break;
+            }
+            padding -= u64::from(bits);
+        }
+
+        self.group_bits = 0;
+        Ok(())
+    }
+
+    // Decodes codes until some output is queued on the stack or input ends
+    fn decode_next(&mut self) -> io::Result<()> {
+        while self.stack.is_empty() && !self.done {
+            if self.free_entry > self.max_code {
+                self.skip_to_group_end()?;
+                self.n_bits += 1;
+                self.max_code = if self.n_bits == self.max_bits {
+                    1 << self.max_bits
+                } else {
+                    (1 << self.n_bits) - 1
+                };
+            }
+
+            let code = match self.read_code(self.n_bits)? {
+                Some(code) => code,
+                None => {
+                    self.done = true;
+                    break;
+                }
+            };
+
+            let old_code = match self.old_code {
+                Some(old_code) => old_code,
+                None => {
+                    if code >= CLEAR_CODE {
+                        return Err(invalid_data("corrupt compress stream"));
+                    }
+                    self.final_char = code as u8;
+                    self.old_code = Some(code);
+                    self.stack.push(self.final_char);
+                    continue;
+                }
+            };
+
+            if code == CLEAR_CODE && self.block_mode {
+                self.free_entry = CLEAR_CODE;
+                self.skip_to_group_end()?;
+                self.n_bits = INIT_BITS;
+                self.max_code = (1 << INIT_BITS) - 1;
+                continue;
+            }
+
+            // Walk the prefix chain, pushing the string in reverse order
+            let in_code = code;
+            let mut code = code;
+            if code >= self.free_entry {
+                if code > self.free_entry {
+                    return Err(invalid_data("corrupt compress stream"));
+                }
+                self.stack.push(self.final_char);
+                code = old_code;
+            }
+            while code >= CLEAR_CODE {
+                if self.stack.len() >= self.prefix.len() {
+                    return Err(invalid_data("corrupt compress stream"));
+                }
+                self.stack.push(self.suffix[code as usize]);
+                code = u32::from(self.prefix[code as usize]);
+            }
+            self.final_char = self.suffix[code as usize];
+            self.stack.push(self.final_char);
+
+            if self.free_entry < 1 << self.max_bits {
+                self.prefix[self.free_entry as usize] = old_code as u16;
+                self.suffix[self.free_entry as usize] = self.final_char;
+                self.free_entry += 1;
+            }
+            self.old_code = Some(in_code);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for LzwDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            if self.stack.is_empty() {
+                self.decode_next()?;
+            }
+            match self.stack.pop() {
+                Some(byte) => {
+                    buf[read] = byte;
+                    read += 1;
+                }
+                None => break,
+            }
+        }
+        Ok(read)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/lzw");
+
+    fn decode(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        LzwDecoder::new(data)?.read_to_end(&mut output)?;
+        Ok(output)
+    }
+
+    fn letters() -> Vec<u8> {
+        let mut state: u32 = 0x12345678;
+        (0..12000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                b'a' + (state % 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decodes_codes_defined_by_themselves() {
+        let mut expected = b"TOBEORNOTTOBEORTOBEORNOT#".repeat(3);
+        expected.extend_from_slice(&[b'a'; 40]);
+        assert_eq!(decode(include_bytes!("../../tests/fixtures/lzw/tobe.Z")).unwrap(), expected);
+    }
+
+    #[test]
+    fn decodes_width_changes_and_clears() {
+        assert_eq!(decode(include_bytes!("../../tests/fixtures/lzw/clear12.Z")).unwrap(), letters());
+    }
+
+    #[test]
+    fn decodes_without_block_mode() {
+        assert_eq!(decode(include_bytes!("../../tests/fixtures/lzw/noblock12.Z")).unwrap(), letters());
+    }
+
+    #[test]
+    fn rejects_bad_streams() {
+        // Wrong magic, a code width over 16 bits, and a code past the table
+        assert!(decode(&[0x1f, 0x8b, 0x90]).is_err());
+        assert!(decode(&[0x1f, 0x9d, 0x91]).is_err());
+        assert!(decode(&[0x1f, 0x9d, 0x90, 0x61, 0x20, 0x03]).is_err());
+    }
+
+    #[test]
+    fn uncompresses_file_next_to_input() {
+        let root = tempfile::tempdir().unwrap();
+        let z_path = root.path().join("letters.txt.Z");
+        std::fs::copy(Path::new(FIXTURES).join("clear12.Z"), &z_path).unwrap();
+        let out_path = uncompress_file(&z_path, None, None).unwrap();
+        assert_eq!(out_path, root.path().join("letters.txt"));
+        assert_eq!(std::fs::read(out_path).unwrap(), letters());
+    }
+}
@@ -22,6 +22,27 @@
 mod zip;
 mod tar;
 mod gzip;
+mod zstd;
+mod xz;
+mod bzip2;
+mod lz4;
+mod brotli;
+mod lzip;
+mod lzw;
+mod zlib;
+mod snappy;
+mod sevenz;
+mod cpio;
+mod ar;
+mod deb;
+mod rpm;
+mod iso;
+mod squashfs;
+mod cab;
+mod oci;
+
+// Import the helpers shared by the archive extractors
+mod archive;
 
 // Import the constants as a module
 mod constants;
@@ -66,6 +87,7 @@ fn main() {
                 let mut file_name: Option<String> = None;
                 let mut encryption_type: Option<String> = None;
                 let mut compression_level: Option<i64> = None;
+                let mut alignment: Option<u16> = None;
                 let mut output_dir: Option<String> = None;
             
                 // Check if -n or --name is provided for a custom name
@@ -88,7 +110,7 @@ fn main() {
                                 i += 1;
                             } else {
                                 println!("Error: You must specify a compression method after -c or --compression.");
-                                println!("Supported encryption types are: bzip2 (bzip), deflate (default), zstd (z)");
+                                println!("Supported encryption types are: bzip2 (bzip), deflate (default), zstd (z), stored (none)");
                                 println!("See --help for more information.");
                                 return;
                             }
@@ -112,6 +134,23 @@ fn main() {
                                 return;
                             }
                         }
+                        "-a" | "--align" => {
+                            if i + 1 < args.len() {
+                                alignment = match args[i + 1].parse::<u16>() {
+                                    Ok(alignment) => Some(alignment),
+                                    Err(_) => {
+                                        println!("Error: Invalid alignment specified.");
+                                        println!("Alignments range from 1 to 65535 bytes.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a number of bytes after -a or --align.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
                         "-o" | "--output" => {
                             if i + 1 < args.len() {
                                 output_dir = Some(args[i + 1].clone());
@@ -132,6 +171,7 @@ fn main() {
                     file_name.as_deref(),
                     encryption_type.as_deref(),
                     compression_level,
+                    alignment,
                     output_dir.as_deref(),
                 ) {
                     Ok(zip_path) => println!("Folder zipped to: {:?}", zip_path),
@@ -154,6 +194,7 @@ fn main() {
 
                 // Check if -n or --name is provided
                 let mut i = 3; // Start checking from index 3 for name-related flags
+                #[allow(clippy::collapsible_match)]
                 while i < args.len() {
                     match args[i].as_str() {
                         "--name" | "-n" => {
@@ -176,7 +217,14 @@ fn main() {
                 // Call the unzip_folder function with the zip file path and file_name
                 let file_name = file_name.as_deref(); // Convert Option<String> to Option<&str>
                 let output_dir = output_dir.as_deref(); // Convert Option<String> to Option<&str>
-                match unzip_file(path, file_name, output_dir) {
+
+                // Cabinets are recognized by their signature and extracted alongside zips
+                let extracted = if cab::cab_utils::is_cab_file(path) {
+                    cab::cab_utils::uncab_file(path, file_name, output_dir)
+                } else {
+                    unzip_file(path, file_name, output_dir)
+                };
+                match extracted {
                     Ok(extracted_path) => {
                         println!("Files extracted to: {:?}", extracted_path);
                     }
@@ -196,6 +244,8 @@ fn main() {
             
                 // Initialize file_name as None by default
                 let mut file_name: Option<String> = None;
+                let mut compression_type: Option<String> = None;
+                let mut compression_level: Option<i64> = None;
                 let mut output_dir: Option<String> = None;
             
                 // Check if -n or --name is provided for a custom name
@@ -212,6 +262,38 @@ fn main() {
                                 return;
                             }
                         }
+                        "-c" | "--compression" => {
+                            if i + 1 < args.len() {
+                                compression_type = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a compression method after -c or --compression.");
+                                println!("Supported tar compression types are: none (default), gzip (gz), zstd (zst), xz, bzip2 (bz2), lz4, lzip (lz)");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<i64>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for gzip.");
+                                println!("Supported levels are: 0 to 9 for xz, bzip2, and lzip.");
+                                println!("For lz4, levels range from 0 to 12, with 3 and above using HC mode.");
+                                println!("For zstd, levels range from -7 to 22.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
                         "-o" | "--output" => {
                             if i + 1 < args.len() {
                                 output_dir = Some(args[i + 1].clone());
@@ -230,6 +312,8 @@ fn main() {
                 match tar::tar_utils::tar_folder(
                     path,
                     file_name.as_deref(),
+                    compression_type.as_deref(),
+                    compression_level,
                     output_dir.as_deref(),
                 ) {
                     Ok(tar_path) => println!("Folder tarred to: {:?}", tar_path),
@@ -299,6 +383,8 @@ fn main() {
                 // Initialize file_name as None by default
                 let mut file_name: Option<String> = None;
                 let mut compression_level: Option<u32> = None;
+                let mut comment: Option<String> = None;
+                let mut no_name = false;
                 let mut output_dir: Option<String> = None;
             
                 // Check if -n or --name is provided for a custom name
@@ -333,6 +419,17 @@ fn main() {
                                 return;
                             }
                         }
+                        "--comment" => {
+                            if i + 1 < args.len() {
+                                comment = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a comment after --comment.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "--no-name" => no_name = true,
                         "-o" | "--output" => {
                             if i + 1 < args.len() {
                                 output_dir = Some(args[i + 1].clone());
@@ -352,6 +449,8 @@ fn main() {
                     path,
                     file_name.as_deref(),
                     compression_level,
+                    comment.as_deref(),
+                    no_name,
                     output_dir.as_deref(),
                 )
                 {
@@ -371,11 +470,15 @@ fn main() {
                 // Initialize file_name as None by default
                 let mut file_name: Option<String> = None;
                 let mut output_dir: Option<String> = None;
+                let mut show_members = false;
+                let mut no_name = false;
 
                 // Check if -n or --name is provided
                 let mut i = 3; // Start checking from index 3 for name-related flags
                 while i < args.len() {
                     match args[i].as_str() {
+                        "-m" | "--members" => show_members = true,
+                        "--no-name" => no_name = true,
                         "-n" | "--name" => {
                             if i + 1 < args.len() {
                                 file_name = Some(args[i + 1].clone());
@@ -402,11 +505,2430 @@ fn main() {
                 }
 
                 match gzip::gzip_utils::gunzip_file(
+                    path,
+                    file_name.as_deref(),
+                    no_name,
+                    output_dir.as_deref(),
+                ) {
+                    Ok(gunzipped) => {
+                        if show_members {
+                            println!("{} gzip member(s):", gunzipped.members.len());
+                            for member in &gunzipped.members {
+                                println!(
+                                    "  offset {:>12}  compressed {:>12}  uncompressed {:>12}",
+                                    member.offset, member.compressed_size, member.uncompressed_size
+                                );
+                            }
+                        }
+                        if let Some(comment) = &gunzipped.comment {
+                            println!("Comment: {}", comment);
+                        }
+                        if let Some(offset) = gunzipped.trailing_garbage {
+                            println!("Warning: trailing garbage at offset {} ignored", offset);
+                        }
+                        println!("File gunzipped to: {:?}", gunzipped.path);
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--zstd" | "-zst" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with zstd.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<i32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<i32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: -7 to 22 for zstd.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zstd::zstd_utils::zstd_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(zst_path) => println!("File compressed to: {:?}", zst_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unzstd" | "-uzst" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a zstd file to decompress.");
+                    return;
+                }
+            
+                let zst_file = &args[2];
+                let path = Path::new(zst_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zstd::zstd_utils::unzstd_file(
                     path,
                     file_name.as_deref(),
                     output_dir.as_deref(),
                 ) {
-                    Ok(unzipped_path) => println!("File gunzipped to: {:?}", unzipped_path),
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--xz" | "-xz" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with xz.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut extreme = false;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for xz and lzma.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-e" | "--extreme" => {
+                            extreme = true;
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match xz::xz_utils::xz_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    extreme,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(xz_path) => println!("File compressed to: {:?}", xz_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--lzma" | "-lzma" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with lzma.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut extreme = false;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for xz and lzma.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-e" | "--extreme" => {
+                            extreme = true;
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match xz::xz_utils::lzma_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    extreme,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(xz_path) => println!("File compressed to: {:?}", xz_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unxz" | "-uxz" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an xz or lzma file to decompress.");
+                    return;
+                }
+            
+                let xz_file = &args[2];
+                let path = Path::new(xz_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match xz::xz_utils::unxz_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--bzip2" | "-bz2" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with bzip2.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for bzip2.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match bzip2::bzip2_utils::bzip2_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(bz2_path) => println!("File compressed to: {:?}", bz2_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--bunzip2" | "-ubz2" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a bzip2 file to decompress.");
+                    return;
+                }
+            
+                let bz2_file = &args[2];
+                let path = Path::new(bz2_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match bzip2::bzip2_utils::bunzip2_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--lz4" | "-lz4" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with lz4.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 12 for lz4, with 3 and above using HC mode.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match lz4::lz4_utils::lz4_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(lz4_path) => println!("File compressed to: {:?}", lz4_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unlz4" | "-ulz4" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an lz4 file to decompress.");
+                    return;
+                }
+            
+                let lz4_file = &args[2];
+                let path = Path::new(lz4_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match lz4::lz4_utils::unlz4_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--brotli" | "-br" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file or folder to compress with brotli.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut window_size: Option<u32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 11 for brotli.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-w" | "--window" => {
+                            if i + 1 < args.len() {
+                                window_size = match args[i + 1].parse::<u32>() {
+                                    Ok(size) => Some(size),
+                                    Err(_) => {
+                                        println!("Error: Invalid window size specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a window size after -w or --window.");
+                                println!("Supported window sizes are: 10 to 24 for brotli.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                // Compress every file in a folder into .br files beside them or under --output
+                if path.is_dir() {
+                    match brotli::brotli_utils::brotli_folder(path, compression_level, window_size, output_dir.as_deref()) {
+                        Ok(br_paths) => println!("Compressed {} files in: {:?}", br_paths.len(), path),
+                        Err(err) => println!("Error: {}", err),
+                    }
+                    return;
+                }
+
+                match brotli::brotli_utils::brotli_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    window_size,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(br_path) => println!("File compressed to: {:?}", br_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unbrotli" | "-ubr" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a brotli file to decompress.");
+                    return;
+                }
+            
+                let br_file = &args[2];
+                let path = Path::new(br_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match brotli::brotli_utils::unbrotli_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--lzip" | "-lz" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with lzip.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for lzip.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match lzip::lzip_utils::lzip_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(lz_path) => println!("File compressed to: {:?}", lz_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unlzip" | "-ulz" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an lzip file to decompress.");
+                    return;
+                }
+            
+                let lz_file = &args[2];
+                let path = Path::new(lz_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match lzip::lzip_utils::unlzip_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--uncompress" | "-uZ" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a compress (.Z) file to decompress.");
+                    return;
+                }
+            
+                let z_file = &args[2];
+                let path = Path::new(z_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match lzw::lzw_utils::uncompress_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--zlib" | "-zl" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with zlib.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for zlib.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zlib::zlib_utils::zlib_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(out_path) => println!("File compressed to: {:?}", out_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unzlib" | "-uzl" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a zlib file to decompress.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zlib::zlib_utils::unzlib_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--deflate" | "-dfl" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with raw deflate.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for raw deflate.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zlib::zlib_utils::deflate_file(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(out_path) => println!("File compressed to: {:?}", out_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--inflate" | "-ifl" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a raw deflate file to decompress.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zlib::zlib_utils::inflate_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--snappy" | "-sz" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a file to compress with snappy.");
+                    return;
+                }
+            
+                let input_file = &args[2];
+                let path = Path::new(input_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+            
+                // Check if -n or --name is provided for a custom name
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match snappy::snappy_utils::snappy_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                )
+                {
+                    Ok(sz_path) => println!("File compressed to: {:?}", sz_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unsnappy" | "-usz" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a snappy file to decompress.");
+                    return;
+                }
+            
+                let sz_file = &args[2];
+                let path = Path::new(sz_file);
+            
+                // Initialize file_name as None by default
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                // Check if -n or --name is provided
+                let mut i = 3; // Start checking from index 3 for name-related flags
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match snappy::snappy_utils::unsnappy_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(unzipped_path) => println!("File decompressed to: {:?}", unzipped_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--un7z" | "-u7z" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a 7z archive to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut password: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-p" | "--password" => {
+                            if i + 1 < args.len() {
+                                password = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a password after -p or --password.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match sevenz::sevenz_utils::un7z_file(
+                    path,
+                    file_name.as_deref(),
+                    password.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(extracted_path) => println!("Files extracted to: {:?}", extracted_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--list7z" | "-ls7z" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a 7z archive to list.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                // The password is only needed for archives with encrypted headers
+                let password = match args.get(3).map(String::as_str) {
+                    Some("--password") | Some("-p") => args.get(4).map(String::as_str),
+                    _ => None,
+                };
+
+                match sevenz::sevenz_utils::list_7z_file(Path::new(&args[2]), password) {
+                    Ok(entries) => {
+                        for entry in entries {
+                            if entry.is_directory() {
+                                println!("{:>12}  {}/", "", entry.name());
+                            } else {
+                                println!("{:>12}  {}", entry.size(), entry.name());
+                            }
+                        }
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--7z" | "-7z" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a folder to compress with 7z.");
+                    return;
+                }
+
+                let folder_dir = &args[2];
+                let path = Path::new(folder_dir);
+
+                let mut file_name: Option<String> = None;
+                let mut compression_level: Option<u32> = None;
+                let mut dictionary_size: Option<u64> = None;
+                let mut solid_block_size: Option<u64> = None;
+                let mut password: Option<String> = None;
+                let mut encrypt_headers = false;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<u32>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("Supported levels are: 0 to 9 for 7z.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-d" | "--dictionary" => {
+                            if i + 1 < args.len() {
+                                dictionary_size = match sevenz::sevenz_utils::parse_size(&args[i + 1]) {
+                                    Ok(size) => Some(size),
+                                    Err(err) => {
+                                        println!("Error: {}", err);
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a size after -d or --dictionary.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-s" | "--solid" => {
+                            if i + 1 < args.len() {
+                                solid_block_size = match args[i + 1].as_str() {
+                                    "off" => Some(0),
+                                    "on" => None,
+                                    size => match sevenz::sevenz_utils::parse_size(size) {
+                                        Ok(size) => Some(size),
+                                        Err(err) => {
+                                            println!("Error: {}", err);
+                                            println!("See --help for more information.");
+                                            return;
+                                        }
+                                    },
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a size, on or off after -s or --solid.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-p" | "--password" => {
+                            if i + 1 < args.len() {
+                                password = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a password after -p or --password.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-eh" | "--encrypt-headers" => {
+                            encrypt_headers = true;
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                if encrypt_headers && password.is_none() {
+                    println!("Error: --encrypt-headers requires a --password.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                match sevenz::sevenz_utils::sevenz_folder(
+                    path,
+                    file_name.as_deref(),
+                    compression_level,
+                    dictionary_size,
+                    solid_block_size,
+                    password.as_deref().map(|password| (password, encrypt_headers)),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(archive_path) => println!("Folder compressed to: {:?}", archive_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--cpio" | "-cpio" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a folder to archive with cpio.");
+                    return;
+                }
+
+                let folder_dir = &args[2];
+                let path = Path::new(folder_dir);
+
+                let mut file_name: Option<String> = None;
+                let mut format: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-f" | "--format" => {
+                            if i + 1 < args.len() {
+                                format = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a format after -f or --format.");
+                                println!("Supported formats are: newc (default), odc, crc.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match cpio::cpio_utils::cpio_folder(
+                    path,
+                    file_name.as_deref(),
+                    format.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(cpio_path) => println!("Folder archived to: {:?}", cpio_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--uncpio" | "-ucpio" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a cpio archive to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match cpio::cpio_utils::uncpio_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(extracted_path) => println!("Files extracted to: {:?}", extracted_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--ar" | "-ar" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a folder to archive with ar.");
+                    return;
+                }
+
+                let folder_dir = &args[2];
+                let path = Path::new(folder_dir);
+
+                let mut file_name: Option<String> = None;
+                let mut variant: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-f" | "--format" => {
+                            if i + 1 < args.len() {
+                                variant = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a variant after -f or --format.");
+                                println!("Supported variants are: gnu (default), bsd.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match ar::ar_utils::ar_folder(
+                    path,
+                    file_name.as_deref(),
+                    variant.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(ar_path) => println!("Folder archived to: {:?}", ar_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unar" | "-uar" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an ar archive to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match ar::ar_utils::unar_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(extracted_path) => println!("Files extracted to: {:?}", extracted_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--listar" | "-lsar" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an ar archive to list.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                match ar::ar_utils::list_ar_file(Path::new(&args[2])) {
+                    Ok(members) => {
+                        for member in members {
+                            println!(
+                                "{:o} {}/{} {:>12}  {}",
+                                member.mode & 0o7777,
+                                member.uid,
+                                member.gid,
+                                member.size,
+                                member.name
+                            );
+                        }
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--undeb" | "-udeb" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a deb package to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match deb::deb_utils::undeb_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(package) => {
+                        print!("{}", package.control);
+                        if !package.control.ends_with('\n') {
+                            println!();
+                        }
+                        if !package.control_files.is_empty() {
+                            println!("Control files: {}", package.control_files.join(", "));
+                        }
+                        println!("Files extracted to: {:?}", package.output_dir);
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unrpm" | "-urpm" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an rpm package to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match rpm::rpm_utils::unrpm_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(package) => {
+                        println!("Name: {}", package.name);
+                        println!("Version: {}-{}", package.version, package.release);
+                        println!("Architecture: {}", package.arch);
+                        println!("Files extracted to: {:?}", package.output_dir);
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--uniso" | "-uiso" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an ISO image to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match iso::iso_utils::uniso_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(output_path) => println!("Image extracted to: {:?}", output_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--listiso" | "-lsiso" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an ISO image to list.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                match iso::iso_utils::list_iso_file(Path::new(&args[2])) {
+                    Ok(entries) => {
+                        for entry in entries {
+                            let suffix = if entry.is_dir { "/" } else { "" };
+                            match &entry.symlink {
+                                Some(target) => println!("{:>12}  {} -> {}", entry.size, entry.path, target),
+                                None => println!("{:>12}  {}{}", entry.size, entry.path, suffix),
+                            }
+                        }
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--unsquashfs" | "-usqfs" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a SquashFS image to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match squashfs::squashfs_utils::unsquashfs_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(output_path) => println!("Image extracted to: {:?}", output_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--listsquashfs" | "-lssqfs" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a SquashFS image to list.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                match squashfs::squashfs_utils::list_squashfs_file(Path::new(&args[2])) {
+                    Ok(entries) => {
+                        for entry in entries {
+                            let suffix = if entry.mode & 0o170000 == 0o040000 { "/" } else { "" };
+                            let target = match &entry.symlink {
+                                Some(target) => format!(" -> {}", target),
+                                None => String::new(),
+                            };
+                            println!(
+                                "{:o} {}/{} {:>12}  {}{}{}",
+                                entry.mode & 0o7777,
+                                entry.uid,
+                                entry.gid,
+                                entry.size,
+                                entry.path,
+                                suffix,
+                                target
+                            );
+                        }
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--uncab" | "-ucab" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a cabinet to extract.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let archive = &args[2];
+                let path = Path::new(archive);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match cab::cab_utils::uncab_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(output_path) => println!("Files extracted to: {:?}", output_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--listcab" | "-lscab" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a cabinet to list.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                match cab::cab_utils::list_cab_file(Path::new(&args[2])) {
+                    Ok(entries) => {
+                        for entry in entries {
+                            println!("{:>12}  {}", entry.size, entry.name);
+                        }
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--oci-layer" | "-oci" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a folder to build the layer from.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let folder_dir = &args[2];
+                let path = Path::new(folder_dir);
+
+                let mut file_name: Option<String> = None;
+                let mut compression_type: Option<String> = None;
+                let mut compression_level: Option<i64> = None;
+                let mut base_dir: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-c" | "--compression" => {
+                            if i + 1 < args.len() {
+                                compression_type = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a compression method after -c or --compression.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<i64>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-b" | "--base" => {
+                            if i + 1 < args.len() {
+                                base_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -b or --base.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match oci::oci_utils::oci_layer_folder(
+                    path,
+                    file_name.as_deref(),
+                    compression_type.as_deref(),
+                    compression_level,
+                    base_dir.as_deref().map(Path::new),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(layer) => {
+                        println!("Layer written to: {:?}", layer.path);
+                        println!("media type: {}", layer.media_type);
+                        println!("diff_id: {}", layer.diff_id);
+                        println!("digest: {}", layer.digest);
+                        println!("size: {}", layer.size);
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--undocker" | "-udkr" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify an image tarball to flatten.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let image = &args[2];
+                let path = Path::new(image);
+
+                let mut file_name: Option<String> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match oci::oci_utils::undocker_file(
+                    path,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(image) => {
+                        if !image.tags.is_empty() {
+                            println!("Image: {}", image.tags.join(", "));
+                        }
+                        println!("Applied {} layers", image.layers);
+                        println!("Root filesystem extracted to: {:?}", image.output_dir);
+                    }
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--package" | "-pkg" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a document folder to package.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let folder_dir = &args[2];
+                let path = Path::new(folder_dir);
+
+                let mut file_name: Option<String> = None;
+                let mut package_type: Option<String> = None;
+                let mut compression_level: Option<i64> = None;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-f" | "--format" => {
+                            if i + 1 < args.len() {
+                                package_type = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a format after -f or --format.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-l" | "--level" => {
+                            if i + 1 < args.len() {
+                                compression_level = match args[i + 1].parse::<i64>() {
+                                    Ok(level) => Some(level),
+                                    Err(_) => {
+                                        println!("Error: Invalid compression level specified.");
+                                        println!("See --help for more information.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a level after -l or --level.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zip::zip_utils::package_folder(
+                    path,
+                    file_name.as_deref(),
+                    package_type.as_deref(),
+                    compression_level,
+                    output_dir.as_deref(),
+                ) {
+                    Ok(package_path) => println!("Folder packaged to: {:?}", package_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--zipalign" | "-za" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a zip file to align.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let zip_file = &args[2];
+                let path = Path::new(zip_file);
+
+                let mut file_name: Option<String> = None;
+                let mut alignment = zip::zip_utils::DEFAULT_ALIGNMENT;
+                let mut output_dir: Option<String> = None;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-n" | "--name" => {
+                            if i + 1 < args.len() {
+                                file_name = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a name after -n or --name.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-a" | "--align" => {
+                            if i + 1 < args.len() {
+                                alignment = match args[i + 1].parse::<u16>() {
+                                    Ok(alignment) => alignment,
+                                    Err(_) => {
+                                        println!("Error: Invalid alignment specified.");
+                                        println!("Alignments range from 1 to 65535 bytes.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a number of bytes after -a or --align.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        "-o" | "--output" => {
+                            if i + 1 < args.len() {
+                                output_dir = Some(args[i + 1].clone());
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a directory after -o or --output.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zip::zip_utils::align_zip(
+                    path,
+                    alignment,
+                    file_name.as_deref(),
+                    output_dir.as_deref(),
+                ) {
+                    Ok(aligned_path) => println!("Zip aligned to: {:?}", aligned_path),
+                    Err(err) => println!("Error: {}", err),
+                }
+            }
+            "--checkalign" | "-ca" => {
+                if args.len() < 3 {
+                    println!("Error: You must specify a zip file to check.");
+                    println!("See --help for more information.");
+                    return;
+                }
+
+                let mut alignment = zip::zip_utils::DEFAULT_ALIGNMENT;
+
+                let mut i = 3;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "-a" | "--align" => {
+                            if i + 1 < args.len() {
+                                alignment = match args[i + 1].parse::<u16>() {
+                                    Ok(alignment) => alignment,
+                                    Err(_) => {
+                                        println!("Error: Invalid alignment specified.");
+                                        println!("Alignments range from 1 to 65535 bytes.");
+                                        return;
+                                    }
+                                };
+                                i += 1;
+                            } else {
+                                println!("Error: You must specify a number of bytes after -a or --align.");
+                                println!("See --help for more information.");
+                                return;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+
+                match zip::zip_utils::check_zip_alignment(Path::new(&args[2]), alignment) {
+                    Ok(entries) => {
+                        let misaligned: Vec<_> = entries.iter().filter(|entry| !entry.aligned).collect();
+                        for entry in &misaligned {
+                            println!("{:>12}  {} (BAD - {})", entry.data_offset, entry.name, entry.data_offset % alignment as u64);
+                        }
+                        if misaligned.is_empty() {
+                            println!("Verification succeeded: {} stored entries aligned to {} bytes", entries.len(), alignment);
+                        } else {
+                            println!("Verification failed: {} of {} stored entries misaligned", misaligned.len(), entries.len());
+                        }
+                    }
                     Err(err) => println!("Error: {}", err),
                 }
             }
@@ -0,0 +1,705 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tar::{Archive, Builder, EntryType, Header};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use anyhow::{self, Result, Context};
+use crate::archive::archive_utils::{link_path, make_node, output_path, set_mode, set_modified, set_owner};
+use crate::tar::tar_utils::{compress_tar, decompressing_reader, tar_compression, tar_output_path, TAR_SUFFIXES};
+
+/// Name prefix marking a path deleted from the layers below.
+pub const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// Marker hiding everything the layers below hold in its directory.
+pub const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+// File type bits of a Unix mode
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+/// A layer tarball written by [`oci_layer_folder`] along with the
+/// identifiers an image config and manifest refer to it by.
+pub struct OciLayer {
+    pub path: PathBuf,
+    pub media_type: &'static str,
+    /// sha256 of the uncompressed tar stream
+    pub diff_id: String,
+    /// sha256 of the file as written
+    pub digest: String,
+    pub size: u64,
+}
+
+// A path that goes into the layer, relative to the layer root
+enum Change {
+    Entry(PathBuf),
+    Whiteout(PathBuf),
+}
+
+/// Writes the contents of `folder_dir` as a reproducible OCI image layer.
+/// Entries are sorted, owned by root and stamped with `SOURCE_DATE_EPOCH`
+/// (or the epoch). Given a `base_dir`, only what differs from it is stored
+/// and paths missing from `folder_dir` become whiteout files.
+pub fn oci_layer_folder(
+    folder_dir: &Path,
+    file_name: Option<&str>,
+    compression_type: Option<&str>,
+    compression_level: Option<i64>,
+    base_dir: Option<&Path>,
+    output_dir: Option<&str>,
+) -> Result<OciLayer> {
+    // Ensure the folders exist
+    if !folder_dir.is_dir() {
+        anyhow::bail!("The provided folder path does not exist or is not a directory.");
+    }
+    if base_dir.is_some_and(|base| !base.is_dir()) {
+        anyhow::bail!("The provided base path does not exist or is not a directory.");
+    }
+
+    // Layers are only defined for plain, gzip and zstd tarballs
+    let media_type = match compression_type {
+        None | Some("none") => "application/vnd.oci.image.layer.v1.tar",
+        Some("gzip") | Some("gz") => "application/vnd.oci.image.layer.v1.tar+gzip",
+        Some("zstd") | Some("zst") => "application/vnd.oci.image.layer.v1.tar+zstd",
+        Some(invalid) => {
+            anyhow::bail!("Invalid layer compression method: '{}'", invalid);
+        }
+    };
+    let (extension, level) = tar_compression(compression_type, compression_level)?;
+    let mtime = source_date_epoch()?;
+
+    // Work out what goes into the layer before creating anything
+    let mut changes = Vec::new();
+    diff_dir(folder_dir, base_dir, Path::new(""), &mut changes)?;
+
+    let full_output_path = tar_output_path(folder_dir, file_name, extension, output_dir)?;
+    let layer_file = File::create(&full_output_path)
+        .with_context(|| format!("Failed to create layer file at {:?}", full_output_path))?;
+
+    // Hash the tar stream on its way into the encoder and the file on its way out
+    let mut diff_id = String::new();
+    let writer = compress_tar(
+        HashingWriter::new(BufWriter::new(layer_file)),
+        extension,
+        level,
+        |writer| {
+            let mut writer = HashingWriter::new(writer);
+            write_layer(folder_dir, &changes, mtime, &mut writer)?;
+            diff_id = writer.digest();
+            Ok(())
+        },
+    )?;
+    let digest = writer.digest();
+    writer.inner.into_inner().map_err(|err| err.into_error())?;
+
+    Ok(OciLayer {
+        size: fs::metadata(&full_output_path)?.len(),
+        path: full_output_path,
+        media_type,
+        diff_id,
+        digest,
+    })
+}
+
+// Reads the timestamp given to every entry, following reproducible-builds.org
+fn source_date_epoch() -> Result<u64> {
+    match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid SOURCE_DATE_EPOCH value '{}'", value)),
+        Err(_) => Ok(0),
+    }
+}
+
+// Collects the changes below `relative`, returning whether there were any.
+// Children are visited in name order so the layer comes out the same on
+// every run.
+fn diff_dir(folder_dir: &Path, base_dir: Option<&Path>, relative: &Path, changes: &mut Vec<Change>) -> Result<bool> {
+    let upper = folder_dir.join(relative);
+    let base = base_dir
+        .map(|base| base.join(relative))
+        .filter(|base| base.symlink_metadata().is_ok_and(|metadata| metadata.is_dir()));
+
+    let mut names = BTreeSet::new();
+    for dir in std::iter::once(&upper).chain(base.as_ref()) {
+        for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+            names.insert(entry.with_context(|| "Failed to read directory entry")?.file_name());
+        }
+    }
+
+    let start = changes.len();
+    for name in names {
+        let path = relative.join(&name);
+        let upper_metadata = match upper.join(&name).symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let mut whiteout = OsString::from(WHITEOUT_PREFIX);
+                whiteout.push(&name);
+                changes.push(Change::Whiteout(relative.join(whiteout)));
+                continue;
+            }
+            Err(err) => return Err(err).with_context(|| format!("Failed to read metadata of {:?}", path)),
+        };
+
+        let base_path = base.as_ref().map(|base| base.join(&name));
+        if upper_metadata.is_dir() {
+            // The directory entry goes in when it is new, differs, or holds changes
+            let index = changes.len();
+            let changed = !unchanged(folder_dir, &path, base_path.as_deref())?;
+            let children = diff_dir(folder_dir, base_dir, &path, changes)?;
+            if changed || children {
+                changes.insert(index, Change::Entry(path));
+            }
+        } else if !unchanged(folder_dir, &path, base_path.as_deref())? {
+            changes.push(Change::Entry(path));
+        }
+    }
+    Ok(changes.len() > start)
+}
+
+// Reports whether the entry at `relative` matches the one at `base_path`.
+// Ownership and timestamps are normalized in the layer, so they are ignored.
+fn unchanged(folder_dir: &Path, relative: &Path, base_path: Option<&Path>) -> Result<bool> {
+    let Some(base_path) = base_path else {
+        return Ok(false);
+    };
+    let Ok(base_metadata) = base_path.symlink_metadata() else {
+        return Ok(false);
+    };
+    let path = folder_dir.join(relative);
+    let metadata = path.symlink_metadata()?;
+    let (mode, rdev) = unix_mode(&metadata);
+    let (base_mode, base_rdev) = unix_mode(&base_metadata);
+    if mode != base_mode {
+        return Ok(false);
+    }
+
+    match mode & S_IFMT {
+        S_IFREG => Ok(metadata.len() == base_metadata.len() && same_contents(&path, base_path)?),
+        S_IFLNK => Ok(fs::read_link(&path)? == fs::read_link(base_path)?),
+        S_IFCHR | S_IFBLK => Ok(rdev == base_rdev),
+        _ => Ok(true),
+    }
+}
+
+// Compares two files of equal length byte for byte
+fn same_contents(path: &Path, other: &Path) -> Result<bool> {
+    let mut file = BufReader::new(File::open(path).with_context(|| format!("Failed to open file {:?}", path))?);
+    let mut other_file = BufReader::new(File::open(other).with_context(|| format!("Failed to open file {:?}", other))?);
+    let mut buffer = [0u8; 8192];
+    let mut other_buffer = [0u8; 8192];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        other_file.read_exact(&mut other_buffer[..read])?;
+        if buffer[..read] != other_buffer[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+// Returns the full Unix mode and device number of an entry on disk
+fn unix_mode(metadata: &fs::Metadata) -> (u32, u64) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        (metadata.mode(), metadata.rdev())
+    }
+    #[cfg(not(unix))]
+    {
+        let file_type = metadata.file_type();
+        let mode = if file_type.is_dir() {
+            S_IFDIR | 0o755
+        } else if file_type.is_symlink() {
+            S_IFLNK | 0o777
+        } else {
+            S_IFREG | 0o644
+        };
+        (mode, 0)
+    }
+}
+
+// Identifies the inode behind a path so hard links are stored once
+fn inode_key(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+// Writes the collected changes as a tar stream with normalized headers
+fn write_layer(folder_dir: &Path, changes: &[Change], mtime: u64, writer: &mut dyn Write) -> Result<()> {
+    let mut tar_builder = Builder::new(writer);
+    let mut links: HashMap<(u64, u64), &Path> = HashMap::new();
+
+    for change in changes {
+        let mut header = Header::new_gnu();
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_mtime(mtime);
+        header.set_size(0);
+
+        let path = match change {
+            Change::Whiteout(path) => {
+                header.set_entry_type(EntryType::Regular);
+                header.set_mode(0o644);
+                tar_builder
+                    .append_data(&mut header, path, io::empty())
+                    .with_context(|| format!("Failed to add whiteout {:?} to layer", path))?;
+                continue;
+            }
+            Change::Entry(path) => path,
+        };
+
+        let source = folder_dir.join(path);
+        let metadata = source
+            .symlink_metadata()
+            .with_context(|| format!("Failed to read metadata of {:?}", source))?;
+        let (mode, rdev) = unix_mode(&metadata);
+        header.set_mode(mode & 0o7777);
+
+        match mode & S_IFMT {
+            S_IFDIR => {
+                header.set_entry_type(EntryType::Directory);
+                tar_builder.append_data(&mut header, path, io::empty())
+            }
+            S_IFREG => {
+                if let Some(key) = inode_key(&metadata) {
+                    if let Some(target) = links.get(&key) {
+                        header.set_entry_type(EntryType::Link);
+                        tar_builder
+                            .append_link(&mut header, path, target)
+                            .with_context(|| format!("Failed to add hard link {:?} to layer", path))?;
+                        continue;
+                    }
+                    links.insert(key, path);
+                }
+                header.set_entry_type(EntryType::Regular);
+                header.set_size(metadata.len());
+                let file = File::open(&source).with_context(|| format!("Failed to open file {:?}", source))?;
+                tar_builder.append_data(&mut header, path, file)
+            }
+            S_IFLNK => {
+                header.set_entry_type(EntryType::Symlink);
+                let target = fs::read_link(&source)?;
+                tar_builder.append_link(&mut header, path, target)
+            }
+            S_IFCHR | S_IFBLK | S_IFIFO => {
+                header.set_entry_type(match mode & S_IFMT {
+                    S_IFCHR => EntryType::Char,
+                    S_IFBLK => EntryType::Block,
+                    _ => EntryType::Fifo,
+                });
+                #[cfg(unix)]
+                {
+                    header.set_device_major(libc::major(rdev as _) as u32)?;
+                    header.set_device_minor(libc::minor(rdev as _) as u32)?;
+                }
+                #[cfg(not(unix))]
+                let _ = rdev;
+                tar_builder.append_data(&mut header, path, io::empty())
+            }
+            // Sockets cannot be stored in a tar archive
+            _ => continue,
+        }
+        .with_context(|| format!("Failed to add {:?} to layer", path))?;
+    }
+
+    tar_builder
+        .into_inner()
+        .with_context(|| "Failed to finalize the layer tar stream")?;
+    Ok(())
+}
+
+// Passes writes through while feeding them into a sha256 digest
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        HashingWriter { inner, hasher: Sha256::new() }
+    }
+
+    fn digest(&self) -> String {
+        format!("sha256:{:x}", self.hasher.clone().finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The image a `docker save` tarball was flattened from.
+pub struct DockerImage {
+    /// Tags the image was saved under, such as `alpine:3.20`.
+    pub tags: Vec<String>,
+    /// Number of layers applied.
+    pub layers: usize,
+    /// Directory the merged root filesystem was written to.
+    pub output_dir: PathBuf,
+}
+
+// Where a member of the image tarball sits
+struct Member {
+    position: u64,
+    size: u64,
+    link: Option<String>,
+}
+
+// Metadata applied to directories once every layer is in place
+struct Directory {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    modified: Option<SystemTime>,
+}
+
+/// Flattens the first image of a `docker save` tarball into a root
+/// filesystem by applying its layers in order, honoring whiteout files and
+/// opaque directories.
+pub fn undocker_file(
+    image_path: &Path,
+    file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<DockerImage> {
+    // Ensure the input image exists
+    if !image_path.is_file() {
+        anyhow::bail!("The provided image path does not exist or is not a file.");
+    }
+
+    // Get the image's file name to use as the base name if no name is provided
+    let image_file_name = image_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine image file name"))?;
+    let base_name = file_name.unwrap_or_else(|| {
+        TAR_SUFFIXES
+            .iter()
+            .find_map(|suffix| image_file_name.strip_suffix(suffix))
+            .unwrap_or(image_file_name)
+    });
+
+    // Index the tarball and read its manifest
+    let (members, manifest) = index_image(image_path)?;
+    let manifest = manifest.ok_or_else(|| anyhow::anyhow!("No manifest.json found; not a docker save tarball"))?;
+    let manifest: Value = serde_json::from_str(&manifest).with_context(|| "Failed to parse manifest.json")?;
+    let image = manifest
+        .as_array()
+        .and_then(|images| images.first())
+        .ok_or_else(|| anyhow::anyhow!("manifest.json lists no images"))?;
+    let layers = image["Layers"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("manifest.json has no Layers list"))?
+        .iter()
+        .map(|layer| layer.as_str().ok_or_else(|| anyhow::anyhow!("Invalid layer name in manifest.json")))
+        .collect::<Result<Vec<_>>>()?;
+    let tags = image["RepoTags"]
+        .as_array()
+        .map(|tags| tags.iter().filter_map(|tag| tag.as_str().map(str::to_owned)).collect())
+        .unwrap_or_default();
+
+    // Determine the base output directory
+    let base_output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => image_path.parent().unwrap_or_else(|| Path::new(".")).join(format!("{}_rootfs", base_name)),
+    };
+
+    // Resolve conflicts by appending a number to the output directory
+    let mut output_path = base_output_path.clone();
+    let mut counter = 1;
+    while output_path.exists() {
+        output_path = base_output_path.with_file_name(format!(
+            "{}-{}",
+            base_output_path.file_name().unwrap().to_str().unwrap(),
+            counter
+        ));
+        counter += 1;
+    }
+
+    // Create the output directory
+    fs::create_dir_all(&output_path)
+        .with_context(|| format!("Failed to create output directory {:?}", output_path))?;
+
+    // Apply the layers from the bottom up
+    let seekable = is_plain_tar(image_path)?;
+    let mut directories = HashMap::new();
+    for layer in &layers {
+        let name = resolve_member(&members, layer)?;
+        let member = &members[name];
+        if seekable {
+            let mut image_file = File::open(image_path)?;
+            image_file.seek(SeekFrom::Start(member.position))?;
+            apply_layer(image_file.take(member.size), &output_path, &mut directories)
+        } else {
+            apply_member(image_path, name, &output_path, &mut directories)
+        }?;
+    }
+
+    // Directory permissions go last so read-only ones do not block later layers
+    let mut directories: Vec<_> = directories.into_iter().collect();
+    directories.sort_by(|a, b| b.0.cmp(&a.0));
+    for (path, directory) in directories {
+        if path.symlink_metadata().is_ok_and(|metadata| metadata.is_dir()) {
+            set_owner(&path, directory.uid, directory.gid)?;
+            set_mode(&path, Some(directory.mode))?;
+            set_modified(&path, directory.modified)?;
+        }
+    }
+
+    Ok(DockerImage {
+        tags,
+        layers: layers.len(),
+        output_dir: output_path,
+    })
+}
+
+// Lists the members of the image tarball and reads its manifest along the way
+fn index_image(image_path: &Path) -> Result<(HashMap<String, Member>, Option<String>)> {
+    let image_file = File::open(image_path)
+        .with_context(|| format!("Failed to open image file {:?}", image_path))?;
+    let reader = decompressing_reader(BufReader::new(image_file))
+        .with_context(|| format!("Failed to read image file {:?}", image_path))?;
+    let mut archive = Archive::new(reader);
+
+    let mut members = HashMap::new();
+    let mut manifest = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = member_name(&entry.path()?.to_string_lossy());
+        let link = match entry.header().entry_type() {
+            EntryType::Symlink | EntryType::Link => entry
+                .link_name()?
+                .map(|target| target.to_string_lossy().into_owned()),
+            _ => None,
+        };
+        if name == "manifest.json" {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            manifest = Some(contents);
+        }
+        members.insert(
+            name,
+            Member {
+                position: entry.raw_file_position(),
+                size: entry.size(),
+                link,
+            },
+        );
+    }
+    Ok((members, manifest))
+}
+
+// Normalizes a member name the way manifest.json spells it
+fn member_name(name: &str) -> String {
+    name.split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Follows symlinked members, as newer Docker releases link the legacy
+// `<id>/layer.tar` paths to `blobs/sha256/<digest>`
+fn resolve_member<'a>(members: &'a HashMap<String, Member>, name: &str) -> Result<&'a str> {
+    let mut name = member_name(name);
+    for _ in 0..16 {
+        let (key, member) = members
+            .get_key_value(&name)
+            .ok_or_else(|| anyhow::anyhow!("Layer {} is missing from the image", name))?;
+        let Some(link) = &member.link else {
+            return Ok(key);
+        };
+        let parent = Path::new(key).parent().map(|parent| parent.to_string_lossy().into_owned());
+        let mut parts: Vec<&str> = parent.as_deref().unwrap_or("").split('/').filter(|part| !part.is_empty()).collect();
+        for part in link.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                part => parts.push(part),
+            }
+        }
+        name = parts.join("/");
+    }
+    anyhow::bail!("Too many levels of links resolving layer {}", name)
+}
+
+// Reports whether the image is an uncompressed tar that layers can be read
+// from in place rather than by scanning the archive again
+fn is_plain_tar(image_path: &Path) -> Result<bool> {
+    let mut header = [0u8; 512];
+    let mut image_file = File::open(image_path)?;
+    Ok(image_file.read_exact(&mut header).is_ok() && &header[257..262] == b"ustar")
+}
+
+// Scans a compressed image tarball for one member and applies it as a layer
+fn apply_member(
+    image_path: &Path,
+    name: &str,
+    output_dir: &Path,
+    directories: &mut HashMap<PathBuf, Directory>,
+) -> Result<()> {
+    let image_file = File::open(image_path)?;
+    let mut archive = Archive::new(decompressing_reader(BufReader::new(image_file))?);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if member_name(&entry.path()?.to_string_lossy()) == name {
+            return apply_layer(entry, output_dir, directories);
+        }
+    }
+    anyhow::bail!("Layer {} is missing from the image", name)
+}
+
+// Applies one layer, which may itself be compressed, on top of `output_dir`
+fn apply_layer<R: Read>(layer: R, output_dir: &Path, directories: &mut HashMap<PathBuf, Directory>) -> Result<()> {
+    let reader = decompressing_reader(BufReader::new(layer))?;
+    let mut archive = Archive::new(reader);
+    archive.set_preserve_permissions(true);
+
+    // Paths this layer provides, which an opaque marker must leave alone
+    let mut written = HashSet::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let out_path = output_path(output_dir, &name)?;
+        if out_path == output_dir {
+            continue;
+        }
+        let file_name = out_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let parent = out_path.parent().unwrap_or(output_dir).to_path_buf();
+
+        // Whiteouts remove what the layers below left behind
+        if file_name == OPAQUE_WHITEOUT {
+            fs::create_dir_all(&parent)?;
+            clear_lower(&parent, &written)?;
+            continue;
+        }
+        if let Some(hidden) = file_name.strip_prefix(WHITEOUT_PREFIX) {
+            if hidden.is_empty() || hidden == "." || hidden == ".." {
+                anyhow::bail!("Refusing to apply unsafe whiteout {:?}", name);
+            }
+            remove_path(&parent.join(hidden))?;
+            continue;
+        }
+
+        for ancestor in out_path.ancestors().take_while(|ancestor| *ancestor != output_dir) {
+            written.insert(ancestor.to_path_buf());
+        }
+        fs::create_dir_all(&parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let mode = header.mode()?;
+        let (uid, gid) = (header.uid()? as u32, header.gid()? as u32);
+        let modified = header.mtime().ok().map(|mtime| UNIX_EPOCH + Duration::from_secs(mtime));
+
+        // An entry replaces whatever the layers below had at its path
+        let existing = out_path.symlink_metadata().ok();
+        if entry_type.is_dir() {
+            if existing.is_some_and(|metadata| !metadata.is_dir()) {
+                remove_path(&out_path)?;
+            }
+            fs::create_dir_all(&out_path)
+                .with_context(|| format!("Failed to create directory {:?}", out_path))?;
+            directories.insert(out_path, Directory { mode, uid, gid, modified });
+            continue;
+        } else if existing.is_some() {
+            remove_path(&out_path)?;
+        }
+
+        match entry_type {
+            EntryType::Link => {
+                let target = entry
+                    .link_name()?
+                    .ok_or_else(|| anyhow::anyhow!("Hard link {:?} has no target", name))?
+                    .to_string_lossy()
+                    .into_owned();
+                let target = link_path(output_dir, &target)?;
+                fs::hard_link(&target, &out_path)
+                    .with_context(|| format!("Failed to create hard link {:?}", out_path))?;
+            }
+            EntryType::Char | EntryType::Block | EntryType::Fifo => {
+                let kind = match entry_type {
+                    EntryType::Char => S_IFCHR,
+                    EntryType::Block => S_IFBLK,
+                    _ => S_IFIFO,
+                };
+                // FIFOs often leave the device fields blank
+                let major = header.device_major().ok().flatten().unwrap_or(0);
+                let minor = header.device_minor().ok().flatten().unwrap_or(0);
+                if make_node(&out_path, kind | (mode & 0o7777), major, minor)? {
+                    set_owner(&out_path, uid, gid)?;
+                    set_mode(&out_path, Some(mode))?;
+                }
+            }
+            EntryType::Regular | EntryType::Continuous | EntryType::Symlink => {
+                entry
+                    .unpack(&out_path)
+                    .with_context(|| format!("Failed to extract {:?}", out_path))?;
+                set_owner(&out_path, uid, gid)?;
+                // Changing the owner drops set-id bits, so the mode goes on again
+                if entry_type != EntryType::Symlink {
+                    set_mode(&out_path, Some(mode))?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Removes everything below `dir` that the current layer did not provide
+fn clear_lower(dir: &Path, written: &HashSet<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {:?}", dir))? {
+        let path = entry?.path();
+        if !written.contains(&path) {
+            remove_path(&path)?;
+        } else if path.symlink_metadata().is_ok_and(|metadata| metadata.is_dir()) {
+            clear_lower(&path, written)?;
+        }
+    }
+    Ok(())
+}
+
+// Removes a file, symlink or directory tree if it exists
+fn remove_path(path: &Path) -> Result<()> {
+    let result = match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    };
+    result.with_context(|| format!("Failed to remove {:?}", path))
+}
@@ -0,0 +1,210 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use anyhow::{self, Result, Context};
+use crate::cpio::cpio_utils::unpack_cpio_stream;
+use crate::tar::tar_utils::decompressing_reader;
+
+// Magic bytes of the lead and of each header structure
+const LEAD_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+const HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8, 0x01];
+const LEAD_SIZE: usize = 96;
+
+// Header tags printed after extraction
+const TAG_NAME: u32 = 1000;
+const TAG_VERSION: u32 = 1001;
+const TAG_RELEASE: u32 = 1002;
+const TAG_ARCH: u32 = 1022;
+const TAG_PAYLOAD_FORMAT: u32 = 1124;
+
+// Header value types holding strings
+const TYPE_STRING: u32 = 6;
+const TYPE_STRING_ARRAY: u32 = 8;
+const TYPE_I18N_STRING: u32 = 9;
+
+/// Basic tags of an RPM package and where its payload was extracted.
+pub struct RpmPackage {
+    pub name: String,
+    pub version: String,
+    pub release: String,
+    pub arch: String,
+    pub output_dir: PathBuf,
+}
+
+pub fn unrpm_file(
+    rpm_file_path: &Path,
+    file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<RpmPackage> {
+    // Ensure the input package exists
+    if !rpm_file_path.is_file() {
+        anyhow::bail!("The provided rpm file path does not exist or is not a file.");
+    }
+
+    // Parse the lead, the signature and the header before touching the disk
+    let rpm_file = File::open(rpm_file_path)
+        .with_context(|| format!("Failed to open rpm file {:?}", rpm_file_path))?;
+    let file_size = rpm_file.metadata()?.len();
+    let mut reader = BufReader::new(rpm_file);
+
+    let mut lead = [0; LEAD_SIZE];
+    reader
+        .read_exact(&mut lead)
+        .with_context(|| format!("{:?} is not an rpm package", rpm_file_path))?;
+    if &lead[..4] != LEAD_MAGIC {
+        anyhow::bail!("{:?} is not an rpm package", rpm_file_path);
+    }
+
+    // The signature is padded so the header starts on an eight byte boundary
+    let signature_size = skip_header(&mut reader, file_size).with_context(|| "Malformed rpm signature")?;
+    let padding = (8 - signature_size % 8) % 8;
+    std::io::copy(&mut (&mut reader).take(padding), &mut std::io::sink())?;
+    let tags = read_header(&mut reader, file_size).with_context(|| "Malformed rpm header")?;
+
+    let tag = |tag: u32| tags.get(&tag).cloned().unwrap_or_default();
+    let payload_format = tags.get(&TAG_PAYLOAD_FORMAT).map_or("cpio", String::as_str);
+    if payload_format != "cpio" {
+        anyhow::bail!("Unsupported rpm payload format: {}", payload_format);
+    }
+
+    // Get the package's name to use as the base name if no name is provided
+    let rpm_file_name = rpm_file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine rpm file name"))?;
+    let base_name = file_name.unwrap_or_else(|| {
+        rpm_file_name.strip_suffix(".rpm").unwrap_or(rpm_file_name)
+    });
+
+    // Determine the base output directory
+    let base_output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => rpm_file_path.parent().unwrap_or_else(|| Path::new(".")).join(format!("{}_extracted", base_name)),
+    };
+
+    // Resolve conflicts by appending a number to the output directory
+    let mut output_path = base_output_path.clone();
+    let mut counter = 1;
+    while output_path.exists() {
+        output_path = base_output_path.with_file_name(format!(
+            "{}-{}",
+            base_output_path.file_name().unwrap().to_str().unwrap(),
+            counter
+        ));
+        counter += 1;
+    }
+
+    // Create the output directory
+    fs::create_dir_all(&output_path)
+        .with_context(|| format!("Failed to create output directory {:?}", output_path))?;
+
+    // The rest of the file is the compressed cpio payload
+    let payload = decompressing_reader(reader)
+        .with_context(|| format!("Failed to read the payload of {:?}", rpm_file_path))?;
+    unpack_cpio_stream(payload, &output_path)?;
+
+    Ok(RpmPackage {
+        name: tag(TAG_NAME),
+        version: tag(TAG_VERSION),
+        release: tag(TAG_RELEASE),
+        arch: tag(TAG_ARCH),
+        output_dir: output_path,
+    })
+}
+
+// Reads the intro of a header structure, returning its index entry count and
+// data size. Both come from the file, so they must fit in its `file_size`.
+fn read_intro<R: Read>(reader: &mut R, file_size: u64) -> Result<(usize, usize)> {
+    let mut intro = [0; 16];
+    reader.read_exact(&mut intro)?;
+    if &intro[..4] != HEADER_MAGIC {
+        anyhow::bail!("Bad header magic");
+    }
+    let entries = u32::from_be_bytes(intro[8..12].try_into()?) as u64;
+    let data_size = u32::from_be_bytes(intro[12..16].try_into()?) as u64;
+    if entries * 16 + data_size > file_size {
+        anyhow::bail!("Header of {} bytes is larger than the file", entries * 16 + data_size);
+    }
+    Ok((entries as usize, data_size as usize))
+}
+
+// Skips a header structure, returning its size without the intro
+fn skip_header<R: Read>(reader: &mut R, file_size: u64) -> Result<u64> {
+    let (entries, data_size) = read_intro(reader, file_size)?;
+    let size = (entries * 16 + data_size) as u64;
+    if std::io::copy(&mut reader.take(size), &mut std::io::sink())? != size {
+        anyhow::bail!("Unexpected end of file");
+    }
+    Ok(size)
+}
+
+// Reads a header structure, keeping the first value of every string tag
+fn read_header<R: Read>(reader: &mut R, file_size: u64) -> Result<HashMap<u32, String>> {
+    let (entries, data_size) = read_intro(reader, file_size)?;
+    let mut index = vec![0; entries * 16];
+    reader.read_exact(&mut index)?;
+    let mut data = vec![0; data_size];
+    reader.read_exact(&mut data)?;
+
+    let mut tags = HashMap::new();
+    for entry in index.chunks_exact(16) {
+        let field = |start: usize| u32::from_be_bytes([entry[start], entry[start + 1], entry[start + 2], entry[start + 3]]);
+        let (tag, kind, offset) = (field(0), field(4), field(8) as usize);
+        if !matches!(kind, TYPE_STRING | TYPE_STRING_ARRAY | TYPE_I18N_STRING) {
+            continue;
+        }
+        let value = data
+            .get(offset..)
+            .and_then(|rest| rest.split(|byte| *byte == 0).next())
+            .ok_or_else(|| anyhow::anyhow!("Header tag {} points outside the header", tag))?;
+        tags.insert(tag, String::from_utf8_lossy(value).into_owned());
+    }
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds a header structure with string tags
+    fn header(tags: &[(u32, &str)]) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for (tag, value) in tags {
+            for field in [*tag, TYPE_STRING, data.len() as u32, 1] {
+                index.extend_from_slice(&field.to_be_bytes());
+            }
+            data.extend_from_slice(value.as_bytes());
+            data.push(0);
+        }
+        let mut header = HEADER_MAGIC.to_vec();
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        header.extend(index);
+        header.extend(data);
+        header
+    }
+
+    #[test]
+    fn reads_string_tags() {
+        let bytes = header(&[(TAG_NAME, "hello"), (TAG_ARCH, "noarch")]);
+        let tags = read_header(&mut &bytes[..], bytes.len() as u64).unwrap();
+        assert_eq!(tags[&TAG_NAME], "hello");
+        assert_eq!(tags[&TAG_ARCH], "noarch");
+    }
+
+    #[test]
+    fn rejects_sizes_larger_than_the_file() {
+        let mut bytes = header(&[(TAG_NAME, "hello")]);
+        bytes[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        bytes[12..16].copy_from_slice(&u32::MAX.to_be_bytes());
+        let err = read_header(&mut &bytes[..], bytes.len() as u64).unwrap_err();
+        assert!(err.to_string().contains("larger than the file"));
+        assert!(skip_header(&mut &bytes[..], bytes.len() as u64).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use sevenz_rust::lzma::LZMA2Options;
+use sevenz_rust::{
+    AesEncoderOptions, Password, SeqReader, SevenZArchiveEntry, SevenZMethodConfiguration,
+    SevenZReader, SevenZWriter, SourceReader,
+};
+use walkdir::WalkDir;
+use crate::archive::archive_utils::{extraction_dir, output_path, set_mode, set_modified};
+use anyhow::{self, Result, Context};
+
+// Windows attribute flag marking the high 16 bits as a Unix st_mode
+const UNIX_EXTENSION: u32 = 0x8000;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x20;
+
+// LZMA2 dictionary sizes accepted by 7-Zip
+const MIN_DICTIONARY_SIZE: u64 = 4 << 10;
+const MAX_DICTIONARY_SIZE: u64 = 1536 << 20;
+
+// File type bits of a Unix st_mode
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+/// Compresses `folder_dir` into a 7z archive using LZMA2.
+///
+/// `solid_block_size` caps the bytes packed into one solid block; `None`
+/// packs every file into a single block and `Some(0)` compresses each file
+/// on its own. `encryption` holds the AES-256 password and whether the
+/// headers, and with them the file names, are encrypted too.
+pub fn sevenz_folder(
+    folder_dir: &Path,
+    file_name: Option<&str>,
+    compression_level: Option<u32>,
+    dictionary_size: Option<u64>,
+    solid_block_size: Option<u64>,
+    encryption: Option<(&str, bool)>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    // Ensure the folder exists
+    if !folder_dir.is_dir() {
+        anyhow::bail!("Provided path is not a directory, or does not exist: {:?}", folder_dir);
+    }
+
+    // Build the LZMA2 coder from the preset, overriding its dictionary if asked
+    let level = compression_level.unwrap_or(6);
+    if level > 9 {
+        anyhow::bail!("Invalid 7z compression level: {} (expected 0 to 9)", level);
+    }
+    let mut lzma2 = LZMA2Options::with_preset(level);
+    if let Some(size) = dictionary_size {
+        if !(MIN_DICTIONARY_SIZE..=MAX_DICTIONARY_SIZE).contains(&size) {
+            anyhow::bail!("Invalid 7z dictionary size: {} (expected 4KiB to 1536MiB)", size);
+        }
+        lzma2.dict_size = size as u32;
+    }
+
+    // Encryption wraps the compressed stream, so the AES coder comes first
+    let mut content_methods: Vec<SevenZMethodConfiguration> = Vec::new();
+    if let Some((password, _)) = encryption {
+        if password.is_empty() {
+            anyhow::bail!("The 7z password must not be empty");
+        }
+        content_methods.push(AesEncoderOptions::new(Password::from(password)).into());
+    }
+    content_methods.push(lzma2.into());
+
+    // Get the folder's name to use as the base file name
+    let folder_name = folder_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Failed to determine folder name"))?;
+
+    // Determine the output path, appending a number on conflicts
+    let base_name = file_name.unwrap_or(folder_name);
+    let output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => folder_dir.parent().unwrap_or_else(|| Path::new(".")).to_path_buf(),
+    };
+    let mut archive_path = output_path.join(format!("{}.7z", base_name));
+    let mut counter = 1;
+    while archive_path.exists() {
+        archive_path = output_path.join(format!("{}-{}.7z", base_name, counter));
+        counter += 1;
+    }
+
+    // Create the 7z file
+    let mut writer = SevenZWriter::create(&archive_path)
+        .with_context(|| format!("Failed to create 7z file at {:?}", archive_path))?;
+    writer.set_content_methods(content_methods);
+    writer.set_encrypt_header(encryption.is_some_and(|(_, headers)| headers));
+
+    // Directories and empty files carry no stream; everything else is grouped
+    // into solid blocks of at most `solid_block_size` bytes
+    let mut block: Vec<(SevenZArchiveEntry, PathBuf)> = Vec::new();
+    let mut block_size = 0;
+    for entry in WalkDir::new(folder_dir).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        let name = path.strip_prefix(folder_dir)?;
+        if name.as_os_str().is_empty() {
+            continue;
+        }
+        let name = name.to_string_lossy().replace('\\', "/");
+
+        // Symlinks are stored as links, with their target as the contents,
+        // rather than as copies of what they point to
+        let metadata = entry.path().symlink_metadata()?;
+        let mut archive_entry = if metadata.file_type().is_symlink() {
+            link_entry(&metadata, name)
+        } else {
+            SevenZArchiveEntry::from_path(path, name)
+        };
+        set_attributes(&mut archive_entry, &metadata);
+
+        let size = if metadata.is_symlink() {
+            fs::read_link(path)?.as_os_str().as_encoded_bytes().len() as u64
+        } else if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        };
+        if size > 0 {
+            if solid_block_size.is_some_and(|limit| block_size + size > limit) && !block.is_empty() {
+                push_block(&mut writer, std::mem::take(&mut block))?;
+                block_size = 0;
+            }
+            block.push((archive_entry, path.to_path_buf()));
+            block_size += size;
+        } else if metadata.is_file() || metadata.is_dir() {
+            writer.push_archive_entry::<&[u8]>(archive_entry, None)?;
+        }
+    }
+    if !block.is_empty() {
+        push_block(&mut writer, block)?;
+    }
+
+    writer.finish().with_context(|| "Failed to finalize the 7z archive")?;
+    Ok(archive_path)
+}
+
+// Compresses a group of files into a single solid block
+fn push_block(
+    writer: &mut SevenZWriter<File>,
+    block: Vec<(SevenZArchiveEntry, PathBuf)>,
+) -> Result<()> {
+    let (entries, paths): (Vec<_>, Vec<_>) = block.into_iter().unzip();
+    let readers = paths
+        .into_iter()
+        .map(|path| SourceReader::new(LazyFile { path, file: None }))
+        .collect::<Vec<_>>();
+    writer.push_archive_entries(entries, SeqReader::new(readers))?;
+    Ok(())
+}
+
+// Builds the entry for a symlink from the link itself, not its target
+fn link_entry(metadata: &fs::Metadata, name: String) -> SevenZArchiveEntry {
+    let mut entry = SevenZArchiveEntry::new();
+    entry.name = name;
+    entry.has_stream = true;
+    if let Some(modified) = metadata.modified().ok().and_then(|time| time.try_into().ok()) {
+        entry.last_modified_date = modified;
+        entry.has_last_modified_date = true;
+    }
+    entry
+}
+
+// Records the file type and Unix permissions the same way 7-Zip and p7zip do
+fn set_attributes(entry: &mut SevenZArchiveEntry, metadata: &fs::Metadata) {
+    let mut attributes = if metadata.is_dir() {
+        FILE_ATTRIBUTE_DIRECTORY
+    } else {
+        FILE_ATTRIBUTE_ARCHIVE
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        attributes |= UNIX_EXTENSION | (metadata.permissions().mode() << 16);
+    }
+    #[cfg(not(unix))]
+    if metadata.is_symlink() {
+        attributes |= UNIX_EXTENSION | ((S_IFLNK | 0o777) << 16);
+    }
+    entry.has_windows_attributes = true;
+    entry.windows_attributes = attributes;
+}
+
+// Opens a file only once a solid block starts reading it and closes it at
+// the end, so large blocks never hold more than one descriptor open.
+// Symlinks read as their target.
+struct LazyFile {
+    path: PathBuf,
+    file: Option<Box<dyn Read>>,
+}
+
+impl Read for LazyFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None if self.path.is_symlink() => {
+                let target = fs::read_link(&self.path)?.into_os_string().into_encoded_bytes();
+                self.file.insert(Box::new(Cursor::new(target)))
+            }
+            None => self.file.insert(Box::new(File::open(&self.path)?)),
+        };
+        let read = file.read(buf)?;
+        if read == 0 {
+            self.file = None;
+        }
+        Ok(read)
+    }
+}
+
+/// Parses a size such as `4096`, `64k`, `32m` or `1g` (binary multiples).
+pub fn parse_size(value: &str) -> Result<u64> {
+    let lower = value.to_ascii_lowercase();
+    let trimmed = lower.trim_end_matches('b');
+    let (digits, shift) = match trimmed.char_indices().last() {
+        Some((i, 'k')) => (&trimmed[..i], 10),
+        Some((i, 'm')) => (&trimmed[..i], 20),
+        Some((i, 'g')) => (&trimmed[..i], 30),
+        _ => (trimmed, 0),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|size| size.checked_mul(1 << shift))
+        .ok_or_else(|| anyhow::anyhow!("Invalid size: '{}'", value))
+}
+
+/// Returns the entries stored in a 7z archive, in archive order.
+pub fn list_7z_file(archive_path: &Path, password: Option<&str>) -> Result<Vec<SevenZArchiveEntry>> {
+    if !archive_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", archive_path);
+    }
+
+    let reader = SevenZReader::open(archive_path, password.map_or_else(Password::empty, Password::from))
+        .map_err(|err| read_error(archive_path, err))?;
+    Ok(reader.archive().files.clone())
+}
+
+pub fn un7z_file(
+    archive_path: &Path,
+    file_name: Option<&str>,
+    password: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    // Ensure the file exists and is a valid file
+    if !archive_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", archive_path);
+    }
+
+    // Determine the base output directory name
+    let base_name = match file_name {
+        Some(name) => name.to_string(),
+        None => archive_path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+
+    // Determine the output directory, numbering it when the name is taken
+    let base_dir = match output_dir {
+        Some(dir) => Path::new(dir),
+        None => archive_path.parent().unwrap_or_else(|| Path::new(".")),
+    };
+    let output_dir = extraction_dir(base_dir, &base_name);
+
+    // Open the archive before creating anything so an unreadable header
+    // leaves no trace
+    let mut reader = SevenZReader::open(archive_path, password.map_or_else(Password::empty, Password::from))
+        .map_err(|err| read_error(archive_path, err))?;
+
+    // Create the output directory
+    fs::create_dir_all(&output_dir)?;
+
+    // Extract each entry in archive order, as solid blocks decode sequentially.
+    // Encrypted contents only fail here, so drop the partial output on errors.
+    let mut directories = Vec::new();
+    let extracted = reader.for_each_entries(|entry, data| {
+        extract_entry(entry, data, &output_dir, &mut directories)
+            .map(|_| true)
+            .map_err(|err| sevenz_rust::Error::other(format!("{:#}", err)))
+    });
+    if let Err(err) = extracted {
+        let _ = fs::remove_dir_all(&output_dir);
+        return Err(read_error(archive_path, err));
+    }
+
+    // Restore directory metadata last, as extracting their contents bumps the
+    // timestamps and a read-only mode would block writing them
+    for (path, modified, mode) in directories.iter().rev() {
+        set_modified(path, *modified)?;
+        set_mode(path, *mode)?;
+    }
+
+    Ok(output_dir)
+}
+
+// Describes a 7z decoding failure, calling out password problems
+fn read_error(archive_path: &Path, err: sevenz_rust::Error) -> anyhow::Error {
+    match err {
+        sevenz_rust::Error::PasswordRequired => {
+            anyhow::anyhow!("{:?} is encrypted, use --password to decrypt it", archive_path)
+        }
+        sevenz_rust::Error::MaybeBadPassword(_) => {
+            anyhow::anyhow!("Wrong password for {:?}, or the archive is corrupted", archive_path)
+        }
+        sevenz_rust::Error::Other(message) => {
+            anyhow::anyhow!("Failed to read 7z archive {:?}: {}", archive_path, message)
+        }
+        err => anyhow::anyhow!("Failed to read 7z archive {:?}: {}", archive_path, err),
+    }
+}
+
+// Writes a single archive entry below `output_dir`
+fn extract_entry(
+    entry: &SevenZArchiveEntry,
+    data: &mut dyn Read,
+    output_dir: &Path,
+    directories: &mut Vec<(PathBuf, Option<SystemTime>, Option<u32>)>,
+) -> Result<()> {
+    // Anti-items only mark deletions in update archives
+    if entry.is_anti_item() {
+        return Ok(());
+    }
+
+    let out_path = output_path(output_dir, entry.name())?;
+    let modified = entry
+        .has_last_modified_date
+        .then(|| SystemTime::from(entry.last_modified_date()));
+    let mode = (entry.has_windows_attributes && entry.windows_attributes() & UNIX_EXTENSION != 0)
+        .then(|| entry.windows_attributes() >> 16);
+
+    if entry.is_directory() {
+        // Create directories
+        fs::create_dir_all(&out_path)
+            .with_context(|| format!("Failed to create directory {:?}", out_path))?;
+        directories.push((out_path, modified, mode));
+        return Ok(());
+    }
+
+    if out_path == output_dir {
+        anyhow::bail!("Archive entry has an empty name");
+    }
+
+    // Create parent directories if needed
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Symlinks store their target as the entry contents
+    #[cfg(unix)]
+    if mode.is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+        let mut target = String::new();
+        data.read_to_string(&mut target)?;
+        std::os::unix::fs::symlink(&target, &out_path)
+            .with_context(|| format!("Failed to create symlink {:?}", out_path))?;
+        return Ok(());
+    }
+
+    // Write the file, which may be empty
+    let mut outfile = BufWriter::new(
+        File::create(&out_path).with_context(|| format!("Failed to create file {:?}", out_path))?,
+    );
+    std::io::copy(data, &mut outfile)?;
+    outfile.into_inner().map_err(|err| err.into_error())?;
+    set_modified(&out_path, modified)?;
+    set_mode(&out_path, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    // Builds a folder with nested, empty and symlinked entries
+    fn sample_folder(root: &Path) -> PathBuf {
+        let folder = root.join("sample");
+        fs::create_dir_all(folder.join("sub/deeper")).unwrap();
+        fs::write(folder.join("a.txt"), "hello\n").unwrap();
+        fs::write(folder.join("sub/b.txt"), "numbers ".repeat(4096)).unwrap();
+        fs::write(folder.join("empty"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("a.txt", folder.join("link")).unwrap();
+        folder
+    }
+
+    fn assert_same_contents(extracted: &Path) {
+        assert_eq!(fs::read_to_string(extracted.join("a.txt")).unwrap(), "hello\n");
+        assert_eq!(fs::read_to_string(extracted.join("sub/b.txt")).unwrap(), "numbers ".repeat(4096));
+        assert_eq!(fs::read(extracted.join("empty")).unwrap().len(), 0);
+        assert!(extracted.join("sub/deeper").is_dir());
+        #[cfg(unix)]
+        assert_eq!(fs::read_link(extracted.join("link")).unwrap(), Path::new("a.txt"));
+    }
+
+    #[test]
+    fn round_trips_through_un7z() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = sample_folder(root.path());
+        let output = root.path().to_str().unwrap();
+        for (name, solid_block_size) in [("solid", None), ("each", Some(0))] {
+            let archive = sevenz_folder(&folder, Some(name), None, None, solid_block_size, None, Some(output)).unwrap();
+            // Empty files and directories are ordinary entries, not deletion markers
+            let entries = list_7z_file(&archive, None).unwrap();
+            assert!(entries.iter().any(|entry| entry.name() == "empty"));
+            assert!(!entries.iter().any(|entry| entry.is_anti_item()));
+            let extracted = un7z_file(&archive, Some(&format!("{}_out", name)), None, Some(output)).unwrap();
+            assert_same_contents(&extracted);
+        }
+    }
+
+    #[test]
+    fn numbers_taken_output_inside_output_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = sample_folder(root.path());
+        let archive_dir = root.path().join("archives");
+        let output = root.path().join("output");
+        fs::create_dir_all(output.join("sample")).unwrap();
+        fs::create_dir(&archive_dir).unwrap();
+        let archive = sevenz_folder(&folder, None, None, None, None, None, Some(archive_dir.to_str().unwrap())).unwrap();
+        let extracted = un7z_file(&archive, None, None, Some(output.to_str().unwrap())).unwrap();
+        assert_eq!(extracted, output.join("sample-1"));
+        assert_same_contents(&extracted);
+    }
+
+    #[test]
+    fn round_trips_encrypted() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = sample_folder(root.path());
+        let output = root.path().to_str().unwrap();
+        for (name, encrypt_headers) in [("contents", false), ("headers", true)] {
+            let archive =
+                sevenz_folder(&folder, Some(name), None, None, None, Some(("secret", encrypt_headers)), Some(output))
+                    .unwrap();
+            // A missing password must not leave an empty folder behind
+            assert!(un7z_file(&archive, Some("locked"), None, Some(output)).is_err());
+            assert!(!root.path().join("locked").exists());
+            let extracted = un7z_file(&archive, Some(&format!("{}_out", name)), Some("secret"), Some(output)).unwrap();
+            assert_same_contents(&extracted);
+        }
+    }
+
+    // libarchive is strict about the header layout, so check that it can
+    // read what we write when bsdtar is installed
+    #[test]
+    fn bsdtar_reads_created_archives() {
+        if Command::new("bsdtar").arg("--version").output().is_err() {
+            eprintln!("bsdtar not found, skipping");
+            return;
+        }
+        let root = tempfile::tempdir().unwrap();
+        let folder = sample_folder(root.path());
+        let output = root.path().to_str().unwrap();
+        for (name, solid_block_size) in [("solid", None), ("each", Some(0))] {
+            let archive = sevenz_folder(&folder, Some(name), None, None, solid_block_size, None, Some(output)).unwrap();
+            let extracted = root.path().join(format!("{}_bsdtar", name));
+            fs::create_dir(&extracted).unwrap();
+            let status = Command::new("bsdtar")
+                .arg("-xf")
+                .arg(&archive)
+                .arg("-C")
+                .arg(&extracted)
+                .status()
+                .unwrap();
+            assert!(status.success(), "bsdtar failed to extract {:?}", archive);
+            assert_same_contents(&extracted);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use snap::read::FrameDecoder;
+use snap::write::FrameEncoder;
+use anyhow::{self, Result};
+
+pub fn snappy_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let sz_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.sz", file_name)));
+
+    let sz_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(sz_name);
+
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(&sz_path)?;
+
+    // Every chunk written by the frame encoder carries a masked CRC32C,
+    // which the frame decoder verifies when reading it back
+    let mut encoder = FrameEncoder::new(BufWriter::new(output_file));
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.into_inner().map_err(|err| err.into_error())?.flush()?;
+
+    Ok(sz_path)
+}
+
+pub fn unsnappy_file(
+    sz_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !sz_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", sz_path);
+    }
+
+    let default_name = sz_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .sz file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| sz_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(sz_path)?;
+    let mut decoder = FrameDecoder::new(BufReader::new(input_file));
+    let mut output_file = File::create(&out_path)?;
+    // A checksum failure should not leave a partial file behind
+    if let Err(err) = std::io::copy(&mut decoder, &mut output_file) {
+        let _ = fs::remove_file(&out_path);
+        return Err(err.into());
+    }
+
+    Ok(out_path)
+}
\ No newline at end of file
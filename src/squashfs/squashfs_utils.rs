@@ -0,0 +1,707 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+// import the necessary modules
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use flate2::read::ZlibDecoder;
+use xz2::read::XzDecoder;
+use xz2::stream::Stream;
+use anyhow::{self, Result, Context};
+use crate::archive::archive_utils::{extraction_dir, make_node, output_path, set_mode, set_modified, set_owner};
+
+// Superblock constants
+const SQUASHFS_MAGIC: u32 = 0x7371_7368;
+const SUPERBLOCK_SIZE: usize = 96;
+const NO_FRAGMENT: u32 = 0xffff_ffff;
+
+// Compressor ids
+const COMPRESSOR_GZIP: u16 = 1;
+const COMPRESSOR_LZMA: u16 = 2;
+const COMPRESSOR_LZO: u16 = 3;
+const COMPRESSOR_XZ: u16 = 4;
+const COMPRESSOR_LZ4: u16 = 5;
+const COMPRESSOR_ZSTD: u16 = 6;
+
+// Metadata blocks hold at most 8 KiB and flag uncompressed blocks in their header
+const METADATA_SIZE: usize = 8192;
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+const DATA_UNCOMPRESSED: u32 = 1 << 24;
+
+// Inode types; the extended variants follow the basic ones
+const BASIC_DIRECTORY: u16 = 1;
+const BASIC_FILE: u16 = 2;
+const BASIC_SYMLINK: u16 = 3;
+const BASIC_BLOCK_DEVICE: u16 = 4;
+const BASIC_CHAR_DEVICE: u16 = 5;
+const BASIC_FIFO: u16 = 6;
+const BASIC_SOCKET: u16 = 7;
+const EXTENDED_OFFSET: u16 = 7;
+
+// File type bits of a Unix st_mode
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+// Guards against directory loops in malformed images
+const MAX_DEPTH: usize = 256;
+
+/// A file, directory, symlink or special file stored in a SquashFS image.
+pub struct SquashfsEntry {
+    pub path: String,
+    /// Full Unix mode, including the file type bits.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub symlink: Option<String>,
+    inode: Inode,
+}
+
+// What an inode points at, beyond its common header
+#[derive(Clone)]
+enum Kind {
+    Directory { block: u32, offset: u16, size: u32 },
+    File { blocks_start: u64, block_sizes: Vec<u32>, fragment: Option<(u32, u32)> },
+    Symlink(String),
+    Device(u32),
+    Special,
+}
+
+#[derive(Clone)]
+struct Inode {
+    kind: Kind,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    modified: SystemTime,
+    number: u32,
+    size: u64,
+}
+
+// An open image with its lookup tables and a cache of decoded metadata blocks
+struct SquashfsImage {
+    file: File,
+    compressor: u16,
+    block_size: u32,
+    root_inode: u64,
+    inode_table_start: u64,
+    directory_table_start: u64,
+    ids: Vec<u32>,
+    fragments: Vec<(u64, u32)>,
+    metadata: HashMap<u64, (Vec<u8>, u64)>,
+}
+
+// Reads a run of metadata that may span several consecutive blocks
+struct MetadataReader<'a> {
+    image: &'a mut SquashfsImage,
+    position: u64,
+    offset: usize,
+}
+
+pub fn list_squashfs_file(squashfs_file_path: &Path) -> Result<Vec<SquashfsEntry>> {
+    // Ensure the file exists and is a valid file
+    if !squashfs_file_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", squashfs_file_path);
+    }
+
+    let mut image = SquashfsImage::open(squashfs_file_path)?;
+    image.entries()
+}
+
+pub fn unsquashfs_file(squashfs_file_path: &Path, file_name: Option<&str>, output_dir: Option<&str>) -> Result<PathBuf> {
+    // Ensure the file exists and is a valid file
+    if !squashfs_file_path.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", squashfs_file_path);
+    }
+
+    // Determine the base output directory name
+    let base_name = match file_name {
+        Some(name) => name.to_string(),
+        None => squashfs_file_path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+    };
+
+    // Determine the output directory, numbering it when the name is taken
+    let base_dir = match output_dir {
+        Some(dir) => Path::new(dir),
+        None => squashfs_file_path.parent().unwrap_or_else(|| Path::new(".")),
+    };
+    let output_dir = extraction_dir(base_dir, &base_name);
+
+    // Read the whole directory tree before creating anything
+    let mut image = SquashfsImage::open(squashfs_file_path)?;
+    let entries = image.entries()?;
+
+    // Create the output directory
+    fs::create_dir_all(&output_dir)?;
+
+    let mut directories = Vec::new();
+    let mut links: HashMap<u32, PathBuf> = HashMap::new();
+    for entry in &entries {
+        let out_path = output_path(&output_dir, &entry.path)?;
+        let inode = &entry.inode;
+
+        match &inode.kind {
+            Kind::Directory { .. } => {
+                fs::create_dir_all(&out_path)
+                    .with_context(|| format!("Failed to create directory {:?}", out_path))?;
+                directories.push((out_path, inode.clone()));
+            }
+            Kind::Symlink(target) => {
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(target, &out_path)
+                    .with_context(|| format!("Failed to create symlink {:?}", out_path))?;
+                #[cfg(not(unix))]
+                let _ = target;
+                set_owner(&out_path, inode.uid, inode.gid)?;
+            }
+            Kind::File { .. } => {
+                // Every name of a hard linked file shares one inode number
+                if let Some(existing) = links.get(&inode.number) {
+                    fs::hard_link(existing, &out_path)
+                        .with_context(|| format!("Failed to create hard link {:?}", out_path))?;
+                    continue;
+                }
+
+                let mut outfile = BufWriter::new(
+                    File::create(&out_path).with_context(|| format!("Failed to create file {:?}", out_path))?,
+                );
+                image.write_file(inode, &mut outfile)?;
+                outfile.into_inner().map_err(|err| err.into_error())?;
+                set_modified(&out_path, Some(inode.modified))?;
+                set_owner(&out_path, inode.uid, inode.gid)?;
+                set_mode(&out_path, Some(inode.mode))?;
+                links.insert(inode.number, out_path);
+            }
+            Kind::Device(device) => {
+                // Device numbers keep the low minor bits apart from the major
+                let major = (device >> 8) & 0xfff;
+                let minor = (device & 0xff) | ((device >> 12) & 0xfff00);
+                if make_node(&out_path, inode.mode, major, minor)? {
+                    set_owner(&out_path, inode.uid, inode.gid)?;
+                    set_mode(&out_path, Some(inode.mode))?;
+                }
+            }
+            Kind::Special => {
+                // FIFOs and sockets are skipped without privileges
+                if make_node(&out_path, inode.mode, 0, 0)? {
+                    set_owner(&out_path, inode.uid, inode.gid)?;
+                    set_mode(&out_path, Some(inode.mode))?;
+                }
+            }
+        }
+    }
+
+    // Restore directory metadata last, as extracting their contents bumps the
+    // timestamps and a read-only mode would block writing them
+    for (path, inode) in directories.iter().rev() {
+        set_modified(path, Some(inode.modified))?;
+        set_owner(path, inode.uid, inode.gid)?;
+        set_mode(path, Some(inode.mode))?;
+    }
+
+    Ok(output_dir)
+}
+
+impl SquashfsImage {
+    // Checks the superblock and loads the id and fragment tables
+    fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path).with_context(|| format!("Failed to open SquashFS image {:?}", path))?;
+        let mut superblock = [0; SUPERBLOCK_SIZE];
+        file.read_exact(&mut superblock)
+            .with_context(|| format!("{:?} is not a SquashFS image", path))?;
+
+        let u16_at = |at: usize| u16::from_le_bytes([superblock[at], superblock[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes(superblock[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(superblock[at..at + 8].try_into().unwrap());
+        if u32_at(0) != SQUASHFS_MAGIC {
+            anyhow::bail!("{:?} is not a SquashFS image", path);
+        }
+        if (u16_at(28), u16_at(30)) != (4, 0) {
+            anyhow::bail!("Unsupported SquashFS version {}.{}", u16_at(28), u16_at(30));
+        }
+
+        let block_size = u32_at(12);
+        if !(4096..=1 << 20).contains(&block_size) || block_size != 1 << u16_at(22) {
+            anyhow::bail!("{:?} has an invalid block size", path);
+        }
+        let compressor = u16_at(20);
+        match compressor {
+            COMPRESSOR_GZIP | COMPRESSOR_LZMA | COMPRESSOR_XZ | COMPRESSOR_LZ4 | COMPRESSOR_ZSTD => {}
+            COMPRESSOR_LZO => anyhow::bail!("LZO compressed SquashFS images are not supported"),
+            other => anyhow::bail!("Unknown SquashFS compressor id {}", other),
+        }
+
+        let mut image = SquashfsImage {
+            file,
+            compressor,
+            block_size,
+            root_inode: u64_at(32),
+            inode_table_start: u64_at(64),
+            directory_table_start: u64_at(72),
+            ids: Vec::new(),
+            fragments: Vec::new(),
+            metadata: HashMap::new(),
+        };
+
+        // Both tables are runs of metadata blocks found through a list of pointers;
+        // the blocks are consecutive, so reading on from the first one is enough
+        let ids = image.read_table(u64_at(48), u16_at(26) as usize * 4)?;
+        image.ids = ids
+            .chunks_exact(4)
+            .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+            .collect();
+        let fragments = image.read_table(u64_at(80), u32_at(16) as usize * 16)?;
+        image.fragments = fragments
+            .chunks_exact(16)
+            .map(|entry| {
+                let start = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                (start, u32::from_le_bytes(entry[8..12].try_into().unwrap()))
+            })
+            .collect();
+
+        Ok(image)
+    }
+
+    // Reads `length` bytes of the table whose first metadata block pointer sits at `start`
+    fn read_table(&mut self, start: u64, length: usize) -> Result<Vec<u8>> {
+        let mut table = vec![0; length];
+        if length == 0 {
+            return Ok(table);
+        }
+        let mut pointer = [0; 8];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut pointer).with_context(|| "Truncated SquashFS lookup table")?;
+        let mut reader = MetadataReader { position: u64::from_le_bytes(pointer), offset: 0, image: self };
+        reader.read_exact(&mut table).with_context(|| "Truncated SquashFS lookup table")?;
+        Ok(table)
+    }
+
+    // Starts reading metadata at a block reference relative to `table_start`
+    fn metadata_reader(&mut self, table_start: u64, reference: u64) -> MetadataReader<'_> {
+        MetadataReader {
+            position: table_start + (reference >> 16),
+            offset: (reference & 0xffff) as usize,
+            image: self,
+        }
+    }
+
+    // Decodes the metadata block at `position`, returning it with the next block's position
+    fn metadata_block(&mut self, position: u64) -> Result<&(Vec<u8>, u64)> {
+        if !self.metadata.contains_key(&position) {
+            let mut header = [0; 2];
+            self.file.seek(SeekFrom::Start(position))?;
+            self.file.read_exact(&mut header)?;
+            let header = u16::from_le_bytes(header);
+            let size = (header & !METADATA_UNCOMPRESSED) as usize;
+            let mut data = vec![0; size];
+            self.file.read_exact(&mut data)?;
+            if header & METADATA_UNCOMPRESSED == 0 {
+                data = self.decompress(&data, METADATA_SIZE)?;
+            }
+            self.metadata.insert(position, (data, position + 2 + size as u64));
+        }
+        Ok(&self.metadata[&position])
+    }
+
+    // Decompresses one block with the image's compressor
+    fn decompress(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let limit = max_size as u64 + 1;
+        match self.compressor {
+            COMPRESSOR_GZIP => {
+                ZlibDecoder::new(data).take(limit).read_to_end(&mut output)?;
+            }
+            COMPRESSOR_LZMA => {
+                XzDecoder::new_stream(data, Stream::new_lzma_decoder(u64::MAX)?)
+                    .take(limit)
+                    .read_to_end(&mut output)?;
+            }
+            COMPRESSOR_XZ => {
+                XzDecoder::new(data).take(limit).read_to_end(&mut output)?;
+            }
+            COMPRESSOR_LZ4 => output = lz4::block::decompress(data, Some(max_size as i32))?,
+            COMPRESSOR_ZSTD => output = zstd::bulk::decompress(data, max_size)?,
+            _ => unreachable!(),
+        }
+        if output.len() > max_size {
+            anyhow::bail!("A compressed block is larger than the image allows");
+        }
+        Ok(output)
+    }
+
+    // Looks up an id table index
+    fn id(&self, index: u16) -> Result<u32> {
+        self.ids
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Inode refers to a missing id {}", index))
+    }
+
+    // Reads the inode at a reference into the inode table
+    fn read_inode(&mut self, reference: u64) -> Result<Inode> {
+        let block_size = self.block_size as u64;
+        let inode_table_start = self.inode_table_start;
+        let mut reader = self.metadata_reader(inode_table_start, reference);
+
+        let inode_type = read_u16(&mut reader)?;
+        let permissions = read_u16(&mut reader)? as u32 & 0o7777;
+        let uid_index = read_u16(&mut reader)?;
+        let gid_index = read_u16(&mut reader)?;
+        let mtime = read_u32(&mut reader)?;
+        let number = read_u32(&mut reader)?;
+
+        let extended = inode_type > EXTENDED_OFFSET;
+        let basic_type = if extended { inode_type - EXTENDED_OFFSET } else { inode_type };
+        let mut size = 0;
+        let (kind, file_type) = match basic_type {
+            BASIC_DIRECTORY if !extended => {
+                let block = read_u32(&mut reader)?;
+                let _link_count = read_u32(&mut reader)?;
+                let listing_size = read_u16(&mut reader)? as u32;
+                let offset = read_u16(&mut reader)?;
+                (Kind::Directory { block, offset, size: listing_size }, S_IFDIR)
+            }
+            BASIC_DIRECTORY => {
+                let _link_count = read_u32(&mut reader)?;
+                let listing_size = read_u32(&mut reader)?;
+                let block = read_u32(&mut reader)?;
+                let _parent = read_u32(&mut reader)?;
+                let _index_count = read_u16(&mut reader)?;
+                let offset = read_u16(&mut reader)?;
+                (Kind::Directory { block, offset, size: listing_size }, S_IFDIR)
+            }
+            BASIC_FILE => {
+                let (blocks_start, fragment, fragment_offset) = if extended {
+                    let blocks_start = read_u64(&mut reader)?;
+                    size = read_u64(&mut reader)?;
+                    let _sparse = read_u64(&mut reader)?;
+                    let _link_count = read_u32(&mut reader)?;
+                    let fragment = read_u32(&mut reader)?;
+                    let fragment_offset = read_u32(&mut reader)?;
+                    let _xattr = read_u32(&mut reader)?;
+                    (blocks_start, fragment, fragment_offset)
+                } else {
+                    let blocks_start = read_u32(&mut reader)? as u64;
+                    let fragment = read_u32(&mut reader)?;
+                    let fragment_offset = read_u32(&mut reader)?;
+                    size = read_u32(&mut reader)? as u64;
+                    (blocks_start, fragment, fragment_offset)
+                };
+
+                // The tail of the file lives in a fragment unless it has its own block
+                let fragment = (fragment != NO_FRAGMENT).then_some((fragment, fragment_offset));
+                let block_count = if fragment.is_some() { size / block_size } else { size.div_ceil(block_size) };
+                let mut block_sizes = Vec::new();
+                for _ in 0..block_count {
+                    block_sizes.push(read_u32(&mut reader)?);
+                }
+                (Kind::File { blocks_start, block_sizes, fragment }, S_IFREG)
+            }
+            BASIC_SYMLINK => {
+                let _link_count = read_u32(&mut reader)?;
+                let target_size = read_u32(&mut reader)?;
+                if target_size > 4096 {
+                    anyhow::bail!("Symlink target is too long");
+                }
+                let mut target = vec![0; target_size as usize];
+                reader.read_exact(&mut target)?;
+                size = target_size as u64;
+                (Kind::Symlink(String::from_utf8_lossy(&target).into_owned()), S_IFLNK)
+            }
+            BASIC_BLOCK_DEVICE | BASIC_CHAR_DEVICE => {
+                let _link_count = read_u32(&mut reader)?;
+                let device = read_u32(&mut reader)?;
+                let file_type = if basic_type == BASIC_BLOCK_DEVICE { S_IFBLK } else { S_IFCHR };
+                (Kind::Device(device), file_type)
+            }
+            BASIC_FIFO => (Kind::Special, S_IFIFO),
+            BASIC_SOCKET => (Kind::Special, S_IFSOCK),
+            other => anyhow::bail!("Unknown SquashFS inode type {}", other),
+        };
+
+        Ok(Inode {
+            kind,
+            mode: file_type | permissions,
+            uid: self.id(uid_index)?,
+            gid: self.id(gid_index)?,
+            modified: UNIX_EPOCH + Duration::from_secs(mtime as u64),
+            number,
+            size,
+        })
+    }
+
+    // Lists every entry of the image, parents before their contents
+    fn entries(&mut self) -> Result<Vec<SquashfsEntry>> {
+        let root = self.read_inode(self.root_inode)?;
+        if !matches!(root.kind, Kind::Directory { .. }) {
+            anyhow::bail!("The root inode is not a directory");
+        }
+        let mut entries = Vec::new();
+        let mut visited = HashSet::new();
+        self.walk(&root, "", 0, &mut visited, &mut entries)?;
+        Ok(entries)
+    }
+
+    fn walk(
+        &mut self,
+        directory: &Inode,
+        prefix: &str,
+        depth: usize,
+        visited: &mut HashSet<u32>,
+        entries: &mut Vec<SquashfsEntry>,
+    ) -> Result<()> {
+        let Kind::Directory { block, offset, size } = directory.kind else {
+            return Ok(());
+        };
+        if depth > MAX_DEPTH || !visited.insert(directory.number) {
+            anyhow::bail!("The directory tree is too deep or contains a loop");
+        }
+
+        // The stored size counts the implicit "." and ".." entries
+        let mut remaining = size.saturating_sub(3) as usize;
+        let directory_table_start = self.directory_table_start;
+        let reference = ((block as u64) << 16) | offset as u64;
+        let mut listing = vec![0; remaining];
+        self.metadata_reader(directory_table_start, reference).read_exact(&mut listing)?;
+        let mut listing = &listing[..];
+
+        // A listing is a series of headers, each followed by entries sharing one inode block
+        let mut children = Vec::new();
+        while remaining >= 12 {
+            let count = read_u32(&mut listing)? as usize + 1;
+            let inode_block = read_u32(&mut listing)? as u64;
+            let _base_number = read_u32(&mut listing)?;
+            remaining -= 12;
+            for _ in 0..count {
+                let inode_offset = read_u16(&mut listing)? as u64;
+                let _number_delta = read_u16(&mut listing)?;
+                let _inode_type = read_u16(&mut listing)?;
+                let name_size = read_u16(&mut listing)? as usize + 1;
+                let mut name = vec![0; name_size];
+                listing.read_exact(&mut name)?;
+                remaining = remaining.saturating_sub(8 + name_size);
+
+                let name = String::from_utf8_lossy(&name).into_owned();
+                if name == "." || name == ".." || name.contains('/') {
+                    anyhow::bail!("Invalid file name {:?} in the image", name);
+                }
+                children.push((name, (inode_block << 16) | inode_offset));
+            }
+        }
+
+        for (name, reference) in children {
+            let inode = self.read_inode(reference)?;
+            let path = format!("{}{}", prefix, name);
+            let is_dir = matches!(inode.kind, Kind::Directory { .. });
+            entries.push(SquashfsEntry {
+                path: path.clone(),
+                mode: inode.mode,
+                uid: inode.uid,
+                gid: inode.gid,
+                size: inode.size,
+                symlink: match &inode.kind {
+                    Kind::Symlink(target) => Some(target.clone()),
+                    _ => None,
+                },
+                inode: inode.clone(),
+            });
+            if is_dir {
+                self.walk(&inode, &format!("{}/", path), depth + 1, visited, entries)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Writes the contents of a regular file from its data blocks and fragment
+    fn write_file<W: Write>(&mut self, inode: &Inode, output: &mut W) -> Result<()> {
+        let Kind::File { blocks_start, block_sizes, fragment } = &inode.kind else {
+            return Ok(());
+        };
+        let block_size = self.block_size as u64;
+        let mut remaining = inode.size;
+        let mut position = *blocks_start;
+
+        for &stored in block_sizes {
+            let length = remaining.min(block_size);
+            let on_disk = stored & !DATA_UNCOMPRESSED;
+            if on_disk == 0 {
+                // Sparse blocks are not stored at all
+                io::copy(&mut io::repeat(0).take(length), output)?;
+            } else {
+                let data = self.read_block(position, stored)?;
+                if (data.len() as u64) < length {
+                    anyhow::bail!("A data block is shorter than expected");
+                }
+                output.write_all(&data[..length as usize])?;
+            }
+            position += on_disk as u64;
+            remaining -= length;
+        }
+
+        if let Some((index, offset)) = fragment {
+            let (start, stored) = *self
+                .fragments
+                .get(*index as usize)
+                .ok_or_else(|| anyhow::anyhow!("Missing fragment {}", index))?;
+            let data = self.read_block(start, stored)?;
+            let tail = data
+                .get(*offset as usize..)
+                .and_then(|rest| rest.get(..remaining as usize))
+                .ok_or_else(|| anyhow::anyhow!("A fragment is shorter than expected"))?;
+            output.write_all(tail)?;
+            remaining = 0;
+        }
+
+        if remaining != 0 {
+            anyhow::bail!("The file's blocks do not cover its size");
+        }
+        Ok(())
+    }
+
+    // Reads and, unless stored raw, decompresses a data or fragment block
+    fn read_block(&mut self, position: u64, stored: u32) -> Result<Vec<u8>> {
+        let on_disk = stored & !DATA_UNCOMPRESSED;
+        if on_disk > self.block_size.max(METADATA_SIZE as u32) * 2 {
+            anyhow::bail!("A data block is larger than the image allows");
+        }
+        let mut data = vec![0; on_disk as usize];
+        self.file.seek(SeekFrom::Start(position))?;
+        self.file.read_exact(&mut data).with_context(|| "Unexpected end of image")?;
+        if stored & DATA_UNCOMPRESSED != 0 {
+            return Ok(data);
+        }
+        self.decompress(&data, self.block_size as usize)
+    }
+}
+
+impl Read for MetadataReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let (data, next) = self.image.metadata_block(self.position).map_err(io::Error::other)?;
+            if self.offset < data.len() {
+                let count = buf.len().min(data.len() - self.offset);
+                buf[..count].copy_from_slice(&data[self.offset..self.offset + count]);
+                self.offset += count;
+                return Ok(count);
+            }
+            if data.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Empty metadata block"));
+            }
+            self.offset -= data.len();
+            self.position = *next;
+        }
+    }
+}
+
+// Little endian integer readers
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/squashfs");
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(FIXTURES).join(name)
+    }
+
+    fn blocks() -> Vec<u8> {
+        (0..10000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn lists_entries() {
+        let entries = list_squashfs_file(&fixture("gzip.sqfs")).unwrap();
+        let find = |path: &str| entries.iter().find(|entry| entry.path == path).unwrap();
+        assert_eq!(find("data").mode, S_IFDIR | 0o750);
+        assert_eq!(find("run.sh").mode, S_IFREG | 0o755);
+        assert_eq!(find("data/blocks.bin").size, 10000);
+        assert_eq!(find("data/sparse.bin").size, 4100);
+        assert_eq!(find("link").symlink.as_deref(), Some("hello.txt"));
+        assert!(entries.iter().any(|entry| entry.path == "empty"));
+    }
+
+    #[test]
+    fn extracts_every_compressor() {
+        let root = tempfile::tempdir().unwrap();
+        for image in ["gzip.sqfs", "xz.sqfs", "lz4.sqfs", "zstd.sqfs", "lzma.sqfs"] {
+            let output = unsquashfs_file(&fixture(image), Some(image), Some(root.path().to_str().unwrap())).unwrap();
+            assert_eq!(fs::read(output.join("hello.txt")).unwrap(), b"hello\n", "{}", image);
+            assert_eq!(fs::read(output.join("data/blocks.bin")).unwrap(), blocks(), "{}", image);
+            let mut sparse = vec![0; 4096];
+            sparse.extend_from_slice(b"end\n");
+            assert_eq!(fs::read(output.join("data/sparse.bin")).unwrap(), sparse, "{}", image);
+            assert!(output.join("empty").is_dir(), "{}", image);
+            let modified = fs::metadata(output.join("run.sh")).unwrap().modified().unwrap();
+            assert_eq!(modified, UNIX_EPOCH + Duration::from_secs(1_700_000_000), "{}", image);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::{MetadataExt, PermissionsExt};
+                assert_eq!(fs::read_link(output.join("link")).unwrap(), Path::new("hello.txt"));
+                let script = fs::metadata(output.join("run.sh")).unwrap();
+                assert_eq!(script.permissions().mode() & 0o777, 0o755, "{}", image);
+                let hello = fs::metadata(output.join("hello.txt")).unwrap();
+                let hardlink = fs::metadata(output.join("data/hardlink")).unwrap();
+                assert_eq!(hello.ino(), hardlink.ino(), "{}", image);
+            }
+        }
+    }
+
+    #[test]
+    fn numbers_taken_output_inside_output_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let output = root.path().join("output");
+        fs::create_dir_all(output.join("gzip")).unwrap();
+        let extracted = unsquashfs_file(&fixture("gzip.sqfs"), None, Some(output.to_str().unwrap())).unwrap();
+        assert_eq!(extracted, output.join("gzip-1"));
+        assert_eq!(fs::read(extracted.join("hello.txt")).unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn rejects_truncated_image() {
+        let root = tempfile::tempdir().unwrap();
+        let data = fs::read(fixture("gzip.sqfs")).unwrap();
+        let inode_table = u64::from_le_bytes(data[64..72].try_into().unwrap()) as usize;
+        let path = root.path().join("truncated.sqfs");
+        fs::write(&path, &data[..inode_table]).unwrap();
+        assert!(list_squashfs_file(&path).is_err());
+        assert!(unsquashfs_file(&path, None, Some(root.path().to_str().unwrap())).is_err());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("not.sqfs");
+        fs::write(&path, vec![0; 4096]).unwrap();
+        let err = list_squashfs_file(&path).err().unwrap();
+        assert!(err.to_string().contains("is not a SquashFS image"));
+    }
+}
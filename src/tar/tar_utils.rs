@@ -2,13 +2,39 @@
 
 // import the necessary modules
 use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use tar::{Builder, Archive};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use bzip2::read::MultiBzDecoder;
+use bzip2::write::BzEncoder;
+use lz4::{Decoder as Lz4Decoder, EncoderBuilder as Lz4EncoderBuilder};
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+use zstd::stream::write::Encoder as ZstdEncoder;
 use anyhow::{self, Result, Context};
+use crate::lzip::lzip_utils::{LzipDecoder, LzipEncoder, LZIP_MAGIC};
+use crate::lzw::lzw_utils::{LzwDecoder, LZW_MAGIC};
+
+// Magic bytes used to detect the compression wrapped around a tar stream
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const LZ4_MAGIC: &[u8] = &[0x04, 0x22, 0x4d, 0x18];
+
+// Suffixes stripped from an archive name to build the default output folder name
+pub const TAR_SUFFIXES: &[&str] = &[".tar.gz", ".tgz", ".tar.zst", ".tzst", ".tar.xz", ".txz", ".tar.bz2", ".tbz2", ".tbz", ".tar.lz4", ".tar.lz", ".tar.Z", ".taZ", ".tar"];
 
 pub fn tar_folder(
     folder_dir: &Path,
     file_name: Option<&str>,
+    compression_type: Option<&str>,
+    compression_level: Option<i64>,
     output_dir: Option<&str>,
 ) -> Result<PathBuf> {
     // Ensure the folder exists
@@ -16,6 +42,45 @@ pub fn tar_folder(
         anyhow::bail!("The provided folder path does not exist or is not a directory.");
     }
 
+    // Determine the archive extension and level for the requested compression
+    let (extension, level) = tar_compression(compression_type, compression_level)?;
+    let full_output_path = tar_output_path(folder_dir, file_name, extension, output_dir)?;
+
+    // Create the tar archive
+    let tar_file = File::create(&full_output_path)
+        .with_context(|| format!("Failed to create tar file at {:?}", full_output_path))?;
+    let writer = compress_tar(BufWriter::new(tar_file), extension, level, |writer| {
+        write_tar_stream(folder_dir, writer).map(|_| ())
+    })?;
+    writer.into_inner().map_err(|err| err.into_error())?;
+
+    Ok(full_output_path)
+}
+
+/// Maps a tar compression method to its archive extension and checked level.
+pub fn tar_compression(compression_type: Option<&str>, compression_level: Option<i64>) -> Result<(&'static str, i64)> {
+    Ok(match compression_type {
+        None | Some("none") => ("tar", 0),
+        Some("gzip") | Some("gz") => ("tar.gz", check_level("gzip", compression_level.unwrap_or(6), 0..=9)?),
+        Some("zstd") | Some("zst") => ("tar.zst", check_level("zstd", compression_level.unwrap_or(3), -7..=22)?),
+        Some("xz") => ("tar.xz", check_level("xz", compression_level.unwrap_or(6), 0..=9)?),
+        Some("bzip2") | Some("bz2") => ("tar.bz2", check_level("bzip2", compression_level.unwrap_or(6), 0..=9)?),
+        Some("lz4") => ("tar.lz4", check_level("lz4", compression_level.unwrap_or(0), 0..=12)?),
+        Some("lzip") | Some("lz") => ("tar.lz", check_level("lzip", compression_level.unwrap_or(6), 0..=9)?),
+        Some(invalid) => {
+            anyhow::bail!("Invalid tar compression method: '{}'", invalid);
+        }
+    })
+}
+
+/// Picks `{name}.{extension}` next to the folder or in `output_dir`,
+/// appending a number when that file already exists.
+pub fn tar_output_path(
+    folder_dir: &Path,
+    file_name: Option<&str>,
+    extension: &str,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
     // Get the folder's name to use as the base file name
     let folder_name = folder_dir
         .file_name()
@@ -24,7 +89,7 @@ pub fn tar_folder(
 
     // Determine the base tar file name
     let base_tar_name = file_name.unwrap_or(folder_name);
-    let mut tar_file_name = format!("{}.tar", base_tar_name);
+    let mut tar_file_name = format!("{}.{}", base_tar_name, extension);
 
     // Determine the output path
     let output_path = match output_dir {
@@ -36,15 +101,84 @@ pub fn tar_folder(
     let mut full_output_path = output_path.join(&tar_file_name);
     let mut counter = 1;
     while full_output_path.exists() {
-        tar_file_name = format!("{}-{}.tar", base_tar_name, counter);
+        tar_file_name = format!("{}-{}.{}", base_tar_name, counter, extension);
         full_output_path = output_path.join(&tar_file_name);
         counter += 1;
     }
 
-    // Create the tar archive
-    let tar_file = File::create(&full_output_path)
-        .with_context(|| format!("Failed to create tar file at {:?}", full_output_path))?;
-    let mut tar_builder = Builder::new(tar_file);
+    Ok(full_output_path)
+}
+
+/// Runs `write_tar` against `writer` wrapped in the encoder for `extension`,
+/// then finalizes the encoder and hands the inner writer back.
+pub fn compress_tar<W: Write>(
+    writer: W,
+    extension: &str,
+    level: i64,
+    write_tar: impl FnOnce(&mut dyn Write) -> Result<()>,
+) -> Result<W> {
+    match extension {
+        "tar.gz" => {
+            let mut encoder = GzEncoder::new(writer, Compression::new(level as u32));
+            write_tar(&mut encoder)?;
+            encoder.finish().with_context(|| "Failed to finalize the gzip stream")
+        }
+        "tar.zst" => {
+            let mut encoder = ZstdEncoder::new(writer, level as i32)?;
+            write_tar(&mut encoder)?;
+            encoder.finish().with_context(|| "Failed to finalize the zstd stream")
+        }
+        "tar.xz" => {
+            let mut encoder = XzEncoder::new(writer, level as u32);
+            write_tar(&mut encoder)?;
+            encoder.finish().with_context(|| "Failed to finalize the xz stream")
+        }
+        "tar.bz2" => {
+            let compression = crate::bzip2::bzip2_utils::compression(Some(level as u32))?;
+            let mut encoder = BzEncoder::new(writer, compression);
+            write_tar(&mut encoder)?;
+            encoder.finish().with_context(|| "Failed to finalize the bzip2 stream")
+        }
+        "tar.lz4" => {
+            let mut encoder = Lz4EncoderBuilder::new()
+                .level(level as u32)
+                .build(writer)?;
+            write_tar(&mut encoder)?;
+            let (writer, result) = encoder.finish();
+            result.with_context(|| "Failed to finalize the lz4 stream")?;
+            Ok(writer)
+        }
+        "tar.lz" => {
+            let mut encoder = LzipEncoder::new(writer, level as u32)?;
+            write_tar(&mut encoder)?;
+            encoder.finish().with_context(|| "Failed to finalize the lzip stream")
+        }
+        _ => {
+            let mut writer = writer;
+            write_tar(&mut writer)?;
+            Ok(writer)
+        }
+    }
+}
+
+// Ensures a compression level lies within the range supported by the codec
+fn check_level(codec: &str, level: i64, range: RangeInclusive<i64>) -> Result<i64> {
+    if !range.contains(&level) {
+        anyhow::bail!(
+            "Invalid {} compression level: {} (expected {} to {})",
+            codec,
+            level,
+            range.start(),
+            range.end()
+        );
+    }
+    Ok(level)
+}
+
+/// Writes the contents of `folder_dir` as a tar stream into `writer`,
+/// returning the writer so the caller can finalize any compression layer.
+pub fn write_tar_stream<W: Write>(folder_dir: &Path, writer: W) -> Result<W> {
+    let mut tar_builder = Builder::new(writer);
 
     // Add the folder's contents to the archive
     for entry in fs::read_dir(folder_dir)
@@ -68,10 +202,8 @@ pub fn tar_folder(
     }
 
     tar_builder
-        .finish()
-        .with_context(|| "Failed to finalize the tar archive")?;
-
-    Ok(full_output_path)
+        .into_inner()
+        .with_context(|| "Failed to finalize the tar archive")
 }
 
 pub fn untar_file(
@@ -90,7 +222,12 @@ pub fn untar_file(
         .and_then(|name| name.to_str())
         .ok_or_else(|| anyhow::anyhow!("Failed to determine tar file name"))?;
 
-    let base_name = file_name.unwrap_or_else(|| tar_file_name.trim_end_matches(".tar"));
+    let base_name = file_name.unwrap_or_else(|| {
+        TAR_SUFFIXES
+            .iter()
+            .find_map(|suffix| tar_file_name.strip_suffix(suffix))
+            .unwrap_or(tar_file_name)
+    });
 
     // Determine the base output directory
     let base_output_path = match output_dir {
@@ -114,15 +251,46 @@ pub fn untar_file(
     fs::create_dir_all(&output_path)
         .with_context(|| format!("Failed to create output directory {:?}", output_path))?;
 
-    // Open the tar file
-    let tar_file = File::open(&tar_file_path)
+    // Open the tar file, transparently decompressing it if needed
+    let tar_file = File::open(tar_file_path)
         .with_context(|| format!("Failed to open tar file {:?}", tar_file_path))?;
+    let reader = decompressing_reader(BufReader::new(tar_file))
+        .with_context(|| format!("Failed to read tar file {:?}", tar_file_path))?;
 
     // Extract the tar archive
-    let mut archive = Archive::new(tar_file);
-    archive
-        .unpack(&output_path)
-        .with_context(|| format!("Failed to extract tar file to {:?}", output_path))?;
+    unpack_tar_stream(reader, &output_path)?;
 
     Ok(output_path)
-}
\ No newline at end of file
+}
+
+/// Unpacks an uncompressed tar stream into `output_path`.
+pub fn unpack_tar_stream<R: Read>(reader: R, output_path: &Path) -> Result<()> {
+    let mut archive = Archive::new(reader);
+    archive
+        .unpack(output_path)
+        .with_context(|| format!("Failed to extract tar file to {:?}", output_path))
+}
+
+/// Wraps `reader` in the decoder matching its leading magic bytes, or
+/// returns it unchanged when no known compression is detected.
+pub fn decompressing_reader<'a, R: BufRead + 'a>(mut reader: R) -> Result<Box<dyn Read + 'a>> {
+    let magic = reader.fill_buf()?;
+
+    if magic.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(MultiGzDecoder::new(reader)))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        Ok(Box::new(ZstdDecoder::with_buffer(reader)?))
+    } else if magic.starts_with(XZ_MAGIC) {
+        Ok(Box::new(XzDecoder::new_multi_decoder(reader)))
+    } else if magic.starts_with(BZIP2_MAGIC) {
+        Ok(Box::new(MultiBzDecoder::new(reader)))
+    } else if magic.starts_with(LZ4_MAGIC) {
+        Ok(Box::new(Lz4Decoder::new(reader)?))
+    } else if magic.starts_with(LZIP_MAGIC) {
+        Ok(Box::new(LzipDecoder::new(reader)))
+    } else if magic.starts_with(LZW_MAGIC) {
+        Ok(Box::new(LzwDecoder::new(reader)?))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use xz2::read::XzDecoder;
+use xz2::stream::{Check, LzmaOptions, Stream, CONCATENATED};
+use xz2::write::XzEncoder;
+use anyhow::{self, Result};
+
+// liblzma flag that trades compression speed for a slightly better ratio
+const PRESET_EXTREME: u32 = 1 << 31;
+
+pub fn xz_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    extreme: bool,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    let stream = Stream::new_easy_encoder(preset(compression_level, extreme)?, Check::Crc64)?;
+    compress_file(input_path, output_file_name, "xz", stream, output_dir)
+}
+
+pub fn lzma_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    extreme: bool,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    let options = LzmaOptions::new_preset(preset(compression_level, extreme)?)?;
+    let stream = Stream::new_lzma_encoder(&options)?;
+    compress_file(input_path, output_file_name, "lzma", stream, output_dir)
+}
+
+pub fn unxz_file(
+    xz_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !xz_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", xz_path);
+    }
+
+    let default_name = xz_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .xz file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| xz_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    // The auto decoder accepts both .xz streams and legacy .lzma files
+    let stream = Stream::new_auto_decoder(u64::MAX, CONCATENATED)?;
+    let input_file = File::open(xz_path)?;
+    let mut decoder = XzDecoder::new_stream(BufReader::new(input_file), stream);
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
+
+/// Builds the liblzma preset for a 0-9 level, optionally with the extreme flag.
+fn preset(compression_level: Option<u32>, extreme: bool) -> Result<u32> {
+    let level = compression_level.unwrap_or(6);
+    if level > 9 {
+        anyhow::bail!("Invalid xz compression level: {} (expected 0 to 9)", level);
+    }
+
+    Ok(if extreme { level | PRESET_EXTREME } else { level })
+}
+
+fn compress_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    extension: &str,
+    stream: Stream,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let xz_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", file_name, extension)));
+
+    let xz_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(xz_name);
+
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(&xz_path)?;
+
+    let mut encoder = XzEncoder::new_stream(BufWriter::new(output_file), stream);
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.finish()?;
+
+    Ok(xz_path)
+}
\ No newline at end of file
@@ -1,18 +1,59 @@
 // Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
 
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 use std::io::Read;
 use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
 use anyhow::{self, Result};
 
+// Media types that identify a document container by its mimetype entry
+const EPUB_MIMETYPE: &str = "application/epub+zip";
+const ODF_MIMETYPE_PREFIX: &str = "application/vnd.oasis.opendocument.";
+
+// OpenDocument kinds and the extensions they are saved with
+const ODF_EXTENSIONS: &[(&str, &str)] = &[
+    ("text", "odt"),
+    ("spreadsheet", "ods"),
+    ("presentation", "odp"),
+    ("graphics", "odg"),
+    ("chart", "odc"),
+    ("formula", "odf"),
+    ("text-template", "ott"),
+    ("spreadsheet-template", "ots"),
+    ("presentation-template", "otp"),
+    ("graphics-template", "otg"),
+    ("text-master", "odm"),
+];
+
+// Main OOXML part folders and the extensions of their documents
+const OOXML_EXTENSIONS: &[(&str, &str)] = &[("word", "docx"), ("xl", "xlsx"), ("ppt", "pptx")];
+
+/// Boundary stored entries are aligned to unless another is given, the
+/// page size mmap-based loaders expect.
+pub const DEFAULT_ALIGNMENT: u16 = 4096;
+
+// Record signatures and fixed sizes from the zip APPNOTE
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+
+// Extra field ids used to pad local headers: the APPNOTE alignment field
+// and the one written by Android's zipalign
+const ALIGNMENT_EXTRA_ID: u16 = 0xa11e;
+const ANDROID_ALIGNMENT_EXTRA_ID: u16 = 0xd935;
+
 pub fn zip_folder(
     folder_dir: &Path,
     file_name: Option<&str>,
     compression_type: Option<&str>,
     compression_level: Option<i64>,
+    alignment: Option<u16>,
     output_dir: Option<&str>,
 ) -> anyhow::Result<PathBuf> {
     // Ensure the folder exists
@@ -46,6 +87,7 @@ pub fn zip_folder(
         Some("bzip2") | Some("bzip") => CompressionMethod::Bzip2,
         Some("deflate") | Some("default") => CompressionMethod::Deflated,
         Some("zstd") | Some("z") => CompressionMethod::Zstd,
+        Some("stored") | Some("none") => CompressionMethod::Stored,
         Some(invalid) => {
             anyhow::bail!("Invalid compression method: '{}'", invalid);
         }
@@ -64,12 +106,22 @@ pub fn zip_folder(
         }
     };
 
+    // Only stored data can be mapped in place, so only it is aligned
+    let alignment = match alignment {
+        Some(0) => anyhow::bail!("Alignment must be at least 1 byte"),
+        Some(_) if compression_method != CompressionMethod::Stored => {
+            anyhow::bail!("Alignment only applies to stored entries; use the stored compression method");
+        }
+        Some(alignment) => alignment,
+        None => 1,
+    };
+
     // Create the ZIP file
     let file = File::create(&zip_path)?;
     let mut zip = ZipWriter::new(file);
     let options = SimpleFileOptions::default()
         .compression_method(compression_method)
-        .compression_level(Some(compression_amount))
+        .compression_level((compression_method != CompressionMethod::Stored).then_some(compression_amount))
         .unix_permissions(0o755);
 
     // Buffer for reading files
@@ -80,7 +132,7 @@ pub fn zip_folder(
 
         // Add files to the ZIP
         if path.is_file() {
-            zip.start_file(name.to_string_lossy(), options)?;
+            zip.start_file(name.to_string_lossy(), options.with_alignment(alignment))?;
             let mut f = File::open(path)?;
             f.read_to_end(&mut buffer)?;
             zip.write_all(&buffer)?;
@@ -95,6 +147,136 @@ pub fn zip_folder(
     Ok(zip_path)
 }
 
+/// Zips a document folder as an EPUB, OpenDocument or Office Open XML
+/// package. The container type comes from `package_type` or is detected from
+/// the folder, and decides the extension and entry layout: a `mimetype`
+/// entry goes first, stored and without extra fields, and
+/// `[Content_Types].xml` leads an OOXML package.
+pub fn package_folder(
+    folder_dir: &Path,
+    file_name: Option<&str>,
+    package_type: Option<&str>,
+    compression_level: Option<i64>,
+    output_dir: Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    // Ensure the folder exists
+    if !folder_dir.is_dir() {
+        anyhow::bail!(
+            "Provided path is not a directory, or does not exist: {:?}",
+            folder_dir
+        );
+    }
+
+    // Read the declared media type, which must not end in a newline
+    let mimetype_path = folder_dir.join("mimetype");
+    let mimetype = match mimetype_path.is_file() {
+        true => Some(std::fs::read_to_string(&mimetype_path)?.trim().to_string()),
+        false => None,
+    };
+    let has_content_types = folder_dir.join("[Content_Types].xml").is_file();
+
+    let package_type = match package_type {
+        Some(package_type) => package_type,
+        None if mimetype.as_deref() == Some(EPUB_MIMETYPE) => "epub",
+        None if mimetype.as_deref().is_some_and(|mimetype| mimetype.starts_with(ODF_MIMETYPE_PREFIX)) => "odf",
+        None if has_content_types => "ooxml",
+        None => anyhow::bail!("Could not detect the package type; expected a mimetype or [Content_Types].xml file"),
+    };
+
+    // Work out the extension and the entry that has to come first
+    let (extension, mimetype) = match package_type {
+        "epub" => {
+            if mimetype.as_deref().is_some_and(|mimetype| mimetype != EPUB_MIMETYPE) {
+                anyhow::bail!("The mimetype file does not declare {}", EPUB_MIMETYPE);
+            }
+            ("epub", Some(EPUB_MIMETYPE.to_string()))
+        }
+        "odf" => {
+            let mimetype = mimetype
+                .filter(|mimetype| mimetype.starts_with(ODF_MIMETYPE_PREFIX))
+                .ok_or_else(|| anyhow::anyhow!("An OpenDocument folder needs a mimetype file declaring its type"))?;
+            let extension = ODF_EXTENSIONS
+                .iter()
+                .find(|(kind, _)| mimetype[ODF_MIMETYPE_PREFIX.len()..] == **kind)
+                .map_or("odf", |(_, extension)| *extension);
+            (extension, Some(mimetype))
+        }
+        "ooxml" => {
+            if !has_content_types {
+                anyhow::bail!("An OOXML folder needs a [Content_Types].xml file");
+            }
+            let extension = OOXML_EXTENSIONS
+                .iter()
+                .find(|(part, _)| folder_dir.join(part).is_dir())
+                .map_or("zip", |(_, extension)| *extension);
+            (extension, None)
+        }
+        invalid => anyhow::bail!("Invalid package type: '{}'", invalid),
+    };
+
+    let folder_name = folder_dir
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Folder has no valid name"))?
+        .to_string_lossy();
+    let base_name = match file_name {
+        Some(name) => name.strip_suffix(&format!(".{}", extension)).unwrap_or(name).to_string(),
+        None => folder_name.into_owned(),
+    };
+
+    // Determine the output directory and resolve name conflicts
+    let output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => folder_dir.parent().unwrap_or_else(|| Path::new(".")).to_path_buf(),
+    };
+    let mut package_path = output_path.join(format!("{}.{}", base_name, extension));
+    let mut counter = 1;
+    while package_path.exists() {
+        package_path = output_path.join(format!("{}-{}.{}", base_name, counter, extension));
+        counter += 1;
+    }
+
+    // Collect the files in a stable order with forward slashes; directory
+    // entries are left out as readers derive them from the paths
+    let mut names = Vec::new();
+    for entry in WalkDir::new(folder_dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let name = entry.path().strip_prefix(folder_dir)?;
+            let name: Vec<_> = name.iter().map(|part| part.to_string_lossy()).collect();
+            names.push(name.join("/"));
+        }
+    }
+    names.retain(|name| name != "mimetype");
+    if package_type == "ooxml" {
+        names.retain(|name| name != "[Content_Types].xml");
+        names.insert(0, "[Content_Types].xml".to_string());
+    }
+
+    let file = File::create(&package_path)?;
+    let mut zip = ZipWriter::new(file);
+
+    // The mimetype is stored first so readers can sniff it at a fixed offset
+    if let Some(mimetype) = mimetype {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("mimetype", options)?;
+        zip.write_all(mimetype.as_bytes())?;
+    }
+
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .compression_level(Some(compression_level.unwrap_or(6)));
+    let mut buffer = Vec::new();
+    for name in names {
+        zip.start_file(name.as_str(), options)?;
+        File::open(folder_dir.join(&name))?.read_to_end(&mut buffer)?;
+        zip.write_all(&buffer)?;
+        buffer.clear();
+    }
+
+    zip.finish()?;
+    Ok(package_path)
+}
+
 pub fn unzip_file(zip_file_dir: &Path, file_name: Option<&str>, output_dir: Option<&str>) -> anyhow::Result<PathBuf> {
     // Ensure the file exists and is a valid file
     if !zip_file_dir.is_file() {
@@ -158,4 +340,289 @@ pub fn unzip_file(zip_file_dir: &Path, file_name: Option<&str>, output_dir: Opti
         }
     }
     Ok(output_dir)
-}
\ No newline at end of file
+}
+
+/// Where the data of a stored entry starts and whether it sits on the
+/// requested boundary.
+pub struct ZipAlignment {
+    pub name: String,
+    pub data_offset: u64,
+    pub aligned: bool,
+}
+
+// An entry as described by the central directory
+struct CentralEntry {
+    name: String,
+    method: u16,
+    compressed_size: u64,
+    header_offset: u64,
+    record: Vec<u8>,
+}
+
+// The central directory and the end record that locates it
+struct CentralDirectory {
+    entries: Vec<CentralEntry>,
+    offset: u64,
+    end_record: Vec<u8>,
+}
+
+/// Rewrites a zip so the data of every stored entry starts on an
+/// `alignment` boundary, like Android's zipalign. Local headers are padded
+/// through an extra field; entry data is copied untouched.
+pub fn align_zip(
+    zip_file_dir: &Path,
+    alignment: u16,
+    file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    // Ensure the file exists and is a valid file
+    if !zip_file_dir.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", zip_file_dir);
+    }
+    if alignment == 0 {
+        anyhow::bail!("Alignment must be at least 1 byte");
+    }
+
+    // Keep the archive's extension, so an .apk stays an .apk
+    let stem = zip_file_dir.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = zip_file_dir.extension().map_or("zip".into(), |extension| extension.to_string_lossy());
+    let base_name = match file_name {
+        Some(name) => name.strip_suffix(&format!(".{}", extension)).unwrap_or(name).to_string(),
+        None => format!("{}_aligned", stem),
+    };
+
+    // Determine the output directory and resolve name conflicts
+    let output_path = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => zip_file_dir.parent().unwrap_or_else(|| Path::new(".")).to_path_buf(),
+    };
+    let mut aligned_path = output_path.join(format!("{}.{}", base_name, extension));
+    let mut counter = 1;
+    while aligned_path.exists() {
+        aligned_path = output_path.join(format!("{}-{}.{}", base_name, counter, extension));
+        counter += 1;
+    }
+
+    let mut file = File::open(zip_file_dir)?;
+    let directory = read_central_directory(&mut file)?;
+
+    // Entries are copied in file order; anything between one local header
+    // and the next, such as a data descriptor, travels with its entry
+    let mut order: Vec<usize> = (0..directory.entries.len()).collect();
+    order.sort_by_key(|&index| directory.entries[index].header_offset);
+    let first_offset = order
+        .first()
+        .map_or(directory.offset, |&index| directory.entries[index].header_offset);
+
+    let mut output = BufWriter::new(File::create(&aligned_path)?);
+    file.seek(SeekFrom::Start(0))?;
+    let mut written = std::io::copy(&mut (&mut file).take(first_offset), &mut output)?;
+
+    let mut new_offsets = vec![0u64; directory.entries.len()];
+    for (position, &index) in order.iter().enumerate() {
+        let entry = &directory.entries[index];
+        let end = order
+            .get(position + 1)
+            .map_or(directory.offset, |&next| directory.entries[next].header_offset);
+
+        // Read the local header, dropping padding left by an earlier alignment
+        let mut header = read_local_header(&mut file, entry)?;
+        let name_length = u16::from_le_bytes([header[26], header[27]]) as usize;
+        let extra = header.split_off(LOCAL_HEADER_SIZE + name_length);
+        let mut extra = strip_alignment_fields(extra);
+
+        if entry.method == 0 && entry.compressed_size > 0 && alignment > 1 {
+            let align = alignment as u64;
+            let data_start = written + header.len() as u64 + extra.len() as u64;
+            if !data_start.is_multiple_of(align) {
+                let mut padding = (align - data_start % align) as usize;
+                while padding < 6 {
+                    padding += align as usize;
+                }
+                extra.extend_from_slice(&ALIGNMENT_EXTRA_ID.to_le_bytes());
+                extra.extend_from_slice(&((padding - 4) as u16).to_le_bytes());
+                extra.extend_from_slice(&alignment.to_le_bytes());
+                extra.resize(extra.len() + padding - 6, 0);
+            }
+        }
+        let extra_length = u16::try_from(extra.len())
+            .map_err(|_| anyhow::anyhow!("Extra field of {} grew past 64 KiB", entry.name))?;
+        header[28..30].copy_from_slice(&extra_length.to_le_bytes());
+
+        new_offsets[index] = written;
+        output.write_all(&header)?;
+        output.write_all(&extra)?;
+        written += (header.len() + extra.len()) as u64;
+
+        // Copy the entry data as is
+        let data_start = file.stream_position()?;
+        let data_length = end
+            .checked_sub(data_start)
+            .ok_or_else(|| anyhow::anyhow!("Entry {} overlaps the next one", entry.name))?;
+        written += std::io::copy(&mut (&mut file).take(data_length), &mut output)?;
+    }
+
+    // Write the central directory with the new header offsets
+    let central_offset = u32::try_from(written)
+        .map_err(|_| anyhow::anyhow!("The aligned archive would need zip64 offsets"))?;
+    for (entry, offset) in directory.entries.iter().zip(new_offsets) {
+        let mut record = entry.record.clone();
+        let offset = u32::try_from(offset)
+            .map_err(|_| anyhow::anyhow!("The aligned archive would need zip64 offsets"))?;
+        record[42..46].copy_from_slice(&offset.to_le_bytes());
+        output.write_all(&record)?;
+    }
+    let mut end_record = directory.end_record;
+    end_record[16..20].copy_from_slice(&central_offset.to_le_bytes());
+    output.write_all(&end_record)?;
+    output.into_inner().map_err(|err| err.into_error())?;
+
+    Ok(aligned_path)
+}
+
+/// Reports where the data of each non-empty stored entry starts and
+/// whether it lies on an `alignment` boundary.
+pub fn check_zip_alignment(zip_file_dir: &Path, alignment: u16) -> anyhow::Result<Vec<ZipAlignment>> {
+    // Ensure the file exists and is a valid file
+    if !zip_file_dir.is_file() {
+        anyhow::bail!("Provided path is not a file, or does not exist: {:?}", zip_file_dir);
+    }
+    if alignment == 0 {
+        anyhow::bail!("Alignment must be at least 1 byte");
+    }
+
+    let mut file = File::open(zip_file_dir)?;
+    let directory = read_central_directory(&mut file)?;
+    let mut report = Vec::new();
+    for entry in &directory.entries {
+        if entry.method != 0 || entry.compressed_size == 0 {
+            continue;
+        }
+        let header_length = read_local_header(&mut file, entry)?.len() as u64;
+        let data_offset = entry.header_offset + header_length;
+        report.push(ZipAlignment {
+            name: entry.name.clone(),
+            data_offset,
+            aligned: data_offset.is_multiple_of(alignment as u64),
+        });
+    }
+    Ok(report)
+}
+
+// Finds the end of central directory record and reads every entry it lists
+fn read_central_directory(file: &mut File) -> anyhow::Result<CentralDirectory> {
+    // The end record sits in the last 64 KiB plus its own size, before the comment
+    let length = file.seek(SeekFrom::End(0))?;
+    let tail_length = length.min((END_OF_CENTRAL_DIRECTORY_SIZE + u16::MAX as usize) as u64);
+    let mut tail = vec![0u8; tail_length as usize];
+    file.seek(SeekFrom::Start(length - tail_length))?;
+    file.read_exact(&mut tail)?;
+
+    let end_position = (0..tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE - 1))
+        .rev()
+        .find(|&position| {
+            read_u32(&tail, position) == END_OF_CENTRAL_DIRECTORY_SIGNATURE
+                && position + END_OF_CENTRAL_DIRECTORY_SIZE + read_u16(&tail, position + 20) as usize == tail.len()
+        })
+        .ok_or_else(|| anyhow::anyhow!("No end of central directory record found; not a zip archive"))?;
+    let end_record = tail[end_position..].to_vec();
+
+    let zip64_locator = end_position
+        .checked_sub(20)
+        .is_some_and(|position| read_u32(&tail, position) == ZIP64_LOCATOR_SIGNATURE);
+    let count = read_u16(&end_record, 10);
+    let size = read_u32(&end_record, 12);
+    let offset = read_u32(&end_record, 16);
+    if zip64_locator || count == u16::MAX || size == u32::MAX || offset == u32::MAX {
+        anyhow::bail!("Zip64 archives are not supported");
+    }
+    if read_u16(&end_record, 4) != 0 || read_u16(&end_record, 8) != count {
+        anyhow::bail!("Archives split across several disks are not supported");
+    }
+
+    let mut central = vec![0u8; size as usize];
+    file.seek(SeekFrom::Start(offset as u64))?;
+    file.read_exact(&mut central)?;
+
+    let mut entries = Vec::with_capacity(count as usize);
+    let mut position = 0;
+    for _ in 0..count {
+        if position + CENTRAL_HEADER_SIZE > central.len() || read_u32(&central, position) != CENTRAL_HEADER_SIGNATURE {
+            anyhow::bail!("Corrupt central directory");
+        }
+        let name_length = read_u16(&central, position + 28) as usize;
+        let record_length = CENTRAL_HEADER_SIZE
+            + name_length
+            + read_u16(&central, position + 30) as usize
+            + read_u16(&central, position + 32) as usize;
+        if position + record_length > central.len() {
+            anyhow::bail!("Corrupt central directory");
+        }
+        let compressed_size = read_u32(&central, position + 20);
+        let header_offset = read_u32(&central, position + 42);
+        if compressed_size == u32::MAX || header_offset == u32::MAX {
+            anyhow::bail!("Zip64 archives are not supported");
+        }
+        let name_start = position + CENTRAL_HEADER_SIZE;
+        entries.push(CentralEntry {
+            name: String::from_utf8_lossy(&central[name_start..name_start + name_length]).into_owned(),
+            method: read_u16(&central, position + 10),
+            compressed_size: compressed_size as u64,
+            header_offset: header_offset as u64,
+            record: central[position..position + record_length].to_vec(),
+        });
+        position += record_length;
+    }
+
+    Ok(CentralDirectory {
+        entries,
+        offset: offset as u64,
+        end_record,
+    })
+}
+
+// Reads the local header of an entry with its name and extra field,
+// leaving the file positioned at the entry data
+fn read_local_header(file: &mut File, entry: &CentralEntry) -> anyhow::Result<Vec<u8>> {
+    let mut header = vec![0u8; LOCAL_HEADER_SIZE];
+    file.seek(SeekFrom::Start(entry.header_offset))?;
+    file.read_exact(&mut header)?;
+    if read_u32(&header, 0) != LOCAL_HEADER_SIGNATURE {
+        anyhow::bail!("Missing local header for {}", entry.name);
+    }
+    let variable_length = read_u16(&header, 26) as usize + read_u16(&header, 28) as usize;
+    header.resize(LOCAL_HEADER_SIZE + variable_length, 0);
+    file.read_exact(&mut header[LOCAL_HEADER_SIZE..])?;
+    Ok(header)
+}
+
+// Drops alignment padding fields from an extra field, keeping it as is
+// when it does not parse as a list of fields
+fn strip_alignment_fields(extra: Vec<u8>) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(extra.len());
+    let mut position = 0;
+    while position + 4 <= extra.len() {
+        let id = read_u16(&extra, position);
+        let end = position + 4 + read_u16(&extra, position + 2) as usize;
+        if end > extra.len() {
+            return extra;
+        }
+        if id != ALIGNMENT_EXTRA_ID && id != ANDROID_ALIGNMENT_EXTRA_ID {
+            stripped.extend_from_slice(&extra[position..end]);
+        }
+        position = end;
+    }
+    // Android pads with stray zero bytes that are not a complete field
+    if extra[position..].iter().any(|&byte| byte != 0) {
+        return extra;
+    }
+    stripped
+}
+
+fn read_u16(bytes: &[u8], position: usize) -> u16 {
+    u16::from_le_bytes([bytes[position], bytes[position + 1]])
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap())
+}
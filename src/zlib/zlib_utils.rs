@@ -0,0 +1,188 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use adler2::Adler32;
+use flate2::Compression;
+use flate2::bufread::DeflateDecoder;
+use flate2::write::{DeflateEncoder, ZlibEncoder};
+use anyhow::{self, Result};
+
+// Compression method nibble for deflate in the zlib CMF byte
+const ZLIB_DEFLATE: u8 = 8;
+
+// FLG bit signalling a preset dictionary follows the header
+const ZLIB_FDICT: u8 = 0x20;
+
+pub fn zlib_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    let (input_file, zlib_path) = open_input(input_path, output_file_name, "zlib", compression_level, output_dir)?;
+    let output_file = File::create(&zlib_path)?;
+
+    let compression = Compression::new(compression_level.unwrap_or(6));
+    let mut encoder = ZlibEncoder::new(BufWriter::new(output_file), compression);
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.finish()?;
+
+    Ok(zlib_path)
+}
+
+pub fn deflate_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    let (input_file, deflate_path) = open_input(input_path, output_file_name, "deflate", compression_level, output_dir)?;
+    let output_file = File::create(&deflate_path)?;
+
+    let compression = Compression::new(compression_level.unwrap_or(6));
+    let mut encoder = DeflateEncoder::new(BufWriter::new(output_file), compression);
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.finish()?;
+
+    Ok(deflate_path)
+}
+
+/// Decompresses a zlib stream, checking the header and verifying the
+/// Adler-32 checksum stored in the trailer against the inflated data.
+pub fn unzlib_file(
+    zlib_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    let out_path = output_path(zlib_path, output_file_name, output_dir)?;
+
+    let mut reader = BufReader::new(File::open(zlib_path)?);
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header)?;
+    if header[0] & 0x0f != ZLIB_DEFLATE || u16::from_be_bytes(header) % 31 != 0 {
+        anyhow::bail!("Not a zlib stream: {:?}", zlib_path);
+    }
+    if header[1] & ZLIB_FDICT != 0 {
+        anyhow::bail!("zlib streams with a preset dictionary are not supported");
+    }
+
+    // A corrupt stream should not leave a partial file behind
+    let output_file = File::create(&out_path)?;
+    if let Err(err) = decode_zlib(DeflateDecoder::new(reader), output_file) {
+        let _ = fs::remove_file(&out_path);
+        return Err(err);
+    }
+
+    Ok(out_path)
+}
+
+// Inflates the deflate data after a zlib header and checks it against the
+// Adler-32 trailer
+fn decode_zlib<R: BufRead>(mut decoder: DeflateDecoder<R>, output_file: File) -> Result<()> {
+    let mut output_file = BufWriter::new(output_file);
+    let mut adler = Adler32::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = decoder.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        adler.write_slice(&buffer[..read]);
+        output_file.write_all(&buffer[..read])?;
+    }
+    output_file.flush()?;
+
+    // The bufread decoder leaves the reader positioned right after the deflate data
+    let mut trailer = [0u8; 4];
+    decoder.into_inner().read_exact(&mut trailer)?;
+    let expected = u32::from_be_bytes(trailer);
+    if expected != adler.checksum() {
+        anyhow::bail!(
+            "zlib Adler-32 mismatch: expected {:08x}, got {:08x}",
+            expected,
+            adler.checksum()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn inflate_file(
+    deflate_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    let out_path = output_path(deflate_path, output_file_name, output_dir)?;
+
+    let input_file = File::open(deflate_path)?;
+    let mut decoder = DeflateDecoder::new(BufReader::new(input_file));
+    let mut output_file = File::create(&out_path)?;
+    if let Err(err) = std::io::copy(&mut decoder, &mut output_file) {
+        let _ = fs::remove_file(&out_path);
+        return Err(err.into());
+    }
+
+    Ok(out_path)
+}
+
+// Validates the input and level, then resolves where the compressed file goes
+fn open_input(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    extension: &str,
+    compression_level: Option<u32>,
+    output_dir: Option<&str>,
+) -> Result<(File, PathBuf)> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    if let Some(level) = compression_level.filter(|level| *level > 9) {
+        anyhow::bail!("Invalid {} compression level: {} (expected 0 to 9)", extension, level);
+    }
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", file_name, extension)));
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    Ok((File::open(input_path)?, out_path))
+}
+
+// Resolves the decompressed file path, defaulting to the input's file stem
+fn output_path(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let default_name = input_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    Ok(output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name))
+}
\ No newline at end of file
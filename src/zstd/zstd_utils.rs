@@ -0,0 +1,80 @@
+// Copyright 2024 the FerrumCrimper authors. All rights reserved. GNUv2 license.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use zstd::stream::read::Decoder;
+use zstd::stream::write::Encoder;
+use anyhow::{self, Result};
+
+pub fn zstd_file(
+    input_path: &Path,
+    output_file_name: Option<&str>,
+    compression_level: Option<i32>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !input_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", input_path);
+    }
+
+    let level = compression_level.unwrap_or(3);
+    if !(-7..=22).contains(&level) {
+        anyhow::bail!("Invalid zstd compression level: {} (expected -7 to 22)", level);
+    }
+
+    let file_name = input_path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let zst_name = output_file_name
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}.zst", file_name)));
+
+    let zst_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| input_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(zst_name);
+
+    let input_file = File::open(input_path)?;
+    let output_file = File::create(&zst_path)?;
+
+    let mut encoder = Encoder::new(BufWriter::new(output_file), level)?;
+    std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+    encoder.finish()?;
+
+    Ok(zst_path)
+}
+
+pub fn unzstd_file(
+    zst_path: &Path,
+    output_file_name: Option<&str>,
+    output_dir: Option<&str>,
+) -> Result<PathBuf> {
+    if !zst_path.is_file() {
+        anyhow::bail!("Provided path is not a file: {:?}", zst_path);
+    }
+
+    let default_name = zst_path
+        .file_stem()
+        .ok_or_else(|| anyhow::anyhow!("Invalid .zst file name"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let out_name = output_file_name
+        .map(String::from)
+        .unwrap_or(default_name);
+
+    let out_path = output_dir
+        .map(Path::new)
+        .unwrap_or_else(|| zst_path.parent().unwrap_or_else(|| Path::new(".")))
+        .join(out_name);
+
+    let input_file = File::open(zst_path)?;
+    let mut decoder = Decoder::new(input_file)?;
+    let mut output_file = File::create(&out_path)?;
+    std::io::copy(&mut decoder, &mut output_file)?;
+
+    Ok(out_path)
+}
\ No newline at end of file